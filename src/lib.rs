@@ -0,0 +1,5511 @@
+//! Installs and updates prebuilt binaries from GitHub, GitLab, and Forgejo releases, crates.io, or plain URLs.
+//!
+//! `load_config` and `new_bin_manager` turn binaries.toml into a [`BinManager`], which the install,
+//! check, and update functions take along with the names of the binaries to act on.
+
+use clap::ValueEnum;
+use flate2::read::GzDecoder;
+use futures::stream::{self, StreamExt};
+use glob::{MatchOptions, Pattern};
+use indicatif::{MultiProgress, ProgressBar, ProgressFinish, ProgressStyle};
+use notify_rust::Notification;
+use platforms::Platform;
+use regex::Regex;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::ffi::OsStr;
+use std::fs::{self, DirEntry};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Cursor, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+use ubi::{ForgeType, UbiBuilder};
+use xz2::read::XzDecoder;
+use zip::ZipArchive;
+
+/// Errors bina reports, each mapped to its own process exit code by `exit_code`
+#[derive(Debug, thiserror::Error)]
+pub enum BinaError {
+    #[error("Config {0} not found")]
+    ConfigNotFound(String),
+    #[error("{0}")]
+    ConfigInvalid(String),
+    #[error("Binary '{0}' not found in the config")]
+    BinaryNotFound(String),
+    #[error("API rate limit exceeded for {0}; set a token or wait for the limit to reset")]
+    RateLimited(String),
+    #[error("No release found for {0}")]
+    ReleaseNotFound(String),
+    #[error("Release asset {0} not found")]
+    AssetNotFound(String),
+    #[error("{0}")]
+    DownloadFailed(String),
+    #[error("{0}")]
+    VerificationFailed(String),
+    /// `check` found binaries in a state its `--fail-if-*` flags reject, or `audit` found vulnerabilities
+    #[error("{0}")]
+    CheckFailed(String),
+    #[error(transparent)]
+    Network(#[from] reqwest::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl BinaError {
+    /// Process exit code, so scripts can tell a network outage from a config mistake
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            BinaError::Other(_) | BinaError::Io(_) => 1,
+            BinaError::ConfigNotFound(_) | BinaError::ConfigInvalid(_) => 3,
+            BinaError::BinaryNotFound(_) => 4,
+            BinaError::Network(_) => 5,
+            BinaError::RateLimited(_) => 6,
+            BinaError::ReleaseNotFound(_) | BinaError::AssetNotFound(_) => 7,
+            BinaError::DownloadFailed(_) => 8,
+            BinaError::VerificationFailed(_) => 9,
+            BinaError::CheckFailed(_) => 10,
+        }
+    }
+}
+
+impl From<String> for BinaError {
+    fn from(message: String) -> Self {
+        BinaError::Other(message)
+    }
+}
+
+impl From<&str> for BinaError {
+    fn from(message: &str) -> Self {
+        BinaError::Other(message.to_string())
+    }
+}
+
+impl From<serde_json::Error> for BinaError {
+    fn from(e: serde_json::Error) -> Self {
+        BinaError::Other(e.to_string())
+    }
+}
+
+impl From<serde_yaml::Error> for BinaError {
+    fn from(e: serde_yaml::Error) -> Self {
+        BinaError::Other(e.to_string())
+    }
+}
+
+impl From<toml::ser::Error> for BinaError {
+    fn from(e: toml::ser::Error) -> Self {
+        BinaError::Other(e.to_string())
+    }
+}
+
+impl From<minisign_verify::Error> for BinaError {
+    fn from(e: minisign_verify::Error) -> Self {
+        BinaError::VerificationFailed(e.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for BinaError {
+    fn from(e: zip::result::ZipError) -> Self {
+        BinaError::DownloadFailed(format!("Unreadable zip archive: {}", e))
+    }
+}
+
+/// Number of GitHub API requests in flight at once
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// Format of a file read by `import_binaries`
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ImportFormat {
+    /// Manifests written by `bina export`, as TOML or JSON
+    Bina,
+    /// mise.toml or .mise.toml `[tools]` tables
+    Mise,
+    /// asdf .tool-versions files
+    Asdf,
+}
+
+/// Version argument nearly every tool understands
+const DEFAULT_VERSION_ARG: &str = "--version";
+
+fn default_version_arg() -> String {
+    DEFAULT_VERSION_ARG.to_string()
+}
+
+fn is_default_version_arg(version_arg: &String) -> bool {
+    version_arg == DEFAULT_VERSION_ARG
+}
+
+/// Permissions of installed executables unless a binary sets `mode`
+const DEFAULT_MODE: u32 = 0o755;
+
+/// Parses octal permissions such as 0755, 755, or 0o755
+fn parse_mode(mode: &str) -> Option<u32> {
+    let digits = mode.strip_prefix("0o").unwrap_or(mode);
+    u32::from_str_radix(digits, 8).ok().filter(|mode| *mode <= 0o7777)
+}
+
+/// Gives an installed executable the binary's permissions; a no-op where there are no permission bits
+fn set_exe_mode(path: &Path, binary: &Binary) -> Result<(), BinaError> {
+    let mode = binary.file_mode()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+        debug!("Set mode {:o} on {}", mode, path.display());
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+    Ok(())
+}
+
+/// Whether the file at `path`, following links, may be executed
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    path.is_file()
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Accepts `exe = "uv"` as well as `exe = ["uv", "uvx"]`
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(exe) => vec![exe],
+        OneOrMany::Many(exes) => exes,
+    })
+}
+
+/// Writes a lone executable back as a plain string
+fn serialize_one_or_many<S: serde::Serializer>(exes: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    match exes {
+        [exe] => serializer.serialize_str(exe),
+        exes => exes.serialize(serializer),
+    }
+}
+
+/// One `[[binaries]]` entry: where a tool is released and how to install and probe it
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Binary {
+    pub name: String,
+    pub repo: String,
+    /// Executable names, the entry name when omitted; the first is the one probed for its version
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        serialize_with = "serialize_one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub exe: Vec<String>,
+    /// Arguments that make the executable print its version, `--version` when omitted
+    #[serde(default = "default_version_arg", skip_serializing_if = "is_default_version_arg")]
+    pub version_arg: String,
+    /// Regex that finds the version in the version output and release tags, instead of `x.y.z`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_regex: Option<String>,
+    /// Release tag to install instead of the latest release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Track pre-releases too when looking for the latest release
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub prerelease: bool,
+    #[serde(default, skip_serializing_if = "Forge::is_github")]
+    pub forge: Forge,
+    /// API root of a self-hosted forge, e.g. https://git.example.com/api/v1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_base_url: Option<String>,
+    /// Direct download URL used instead of forge releases
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Base URLs tried in order when a download fails, each replacing the scheme and host of the asset URL
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+    /// Substring that picks the release asset when several fit the platform
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matching: Option<String>,
+    /// Regex an asset name must match to be considered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matching_regex: Option<String>,
+    /// C library the Linux asset is built against, overriding the global setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub libc: Option<Libc>,
+    /// Expected SHA-256 of the downloaded release asset for the pinned version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// URL of the asset's detached signature; `{url}` expands to the asset URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_pattern: Option<String>,
+    /// Minisign public key, or the path of an armored GPG public key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+    /// Refuse release assets without a GitHub artifact attestation from this repository
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub require_attestation: bool,
+    /// Regex the signing certificate's identity must match for cosign keyless verification, e.g. the release workflow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cosign_identity: Option<String>,
+    /// OIDC issuer of the signing certificate; GitHub Actions when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cosign_issuer: Option<String>,
+    /// URL of the asset's Sigstore bundle, `{url}` expanding to the asset URL; `{url}.sigstore.json` when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cosign_bundle: Option<String>,
+    #[serde(default, skip_serializing_if = "Source::is_release")]
+    pub source: Source,
+    /// Directory to install into instead of XDG_BIN_HOME; a leading `~/` expands to HOME
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub install_dir: Option<String>,
+    /// Groups for selecting binaries with --tag
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Hosts the binary is installed on, as `os` or `os-arch`; empty means everywhere
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub platforms: Vec<String>,
+    /// Machine names, or glob patterns such as `server-*`, the binary is meant for; empty means every machine
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only_hosts: Vec<String>,
+    /// Profiles the binary belongs to, one of which BINA_PROFILE must select; empty means every profile
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<String>,
+    /// Install man pages found in the release archive into $XDG_DATA_HOME/man
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub man_pages: bool,
+    /// Install shell completions found in the release archive for bash, zsh, and fish
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub completions: bool,
+    /// Octal permissions of the installed executables, e.g. "0750"; 0755 when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Extra names for the executable, linked next to it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Shell commands run after each install, with the binary's directory first on PATH
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_install: Vec<String>,
+    /// Let `bina watch` install new releases as soon as it sees them
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub auto_update: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub linux: Option<PlatformOverride>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub macos: Option<PlatformOverride>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub windows: Option<PlatformOverride>,
+}
+
+/// Fields of a binary that differ on one operating system
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PlatformOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exe: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_arg: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matching: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matching_regex: Option<String>,
+}
+
+impl Binary {
+    /// Executable probed for the version and recorded as the install's path
+    pub fn main_exe(&self) -> &str {
+        self.exe.first().map_or(self.name.as_str(), String::as_str)
+    }
+
+    /// Permission bits the executables are installed with
+    fn file_mode(&self) -> Result<u32, BinaError> {
+        match &self.mode {
+            Some(mode) => parse_mode(mode).ok_or_else(|| {
+                BinaError::ConfigInvalid(format!("Binary '{}' has an invalid mode \"{}\"", self.name, mode))
+            }),
+            None => Ok(DEFAULT_MODE),
+        }
+    }
+
+    /// Whether `platforms` includes the target platform
+    fn supports_target(&self, manager: &BinManager) -> bool {
+        let target = format!("{}-{}", manager.target_os(), manager.target_arch());
+        self.platforms.is_empty()
+            || self
+                .platforms
+                .iter()
+                .any(|platform| *platform == target || platform == manager.target_os())
+    }
+
+    /// Whether `only_hosts` and `profiles` include this machine and the selected profile
+    fn applies_here(&self, manager: &BinManager) -> bool {
+        let host_matches = self.only_hosts.is_empty()
+            || manager.host.as_deref().is_some_and(|host| {
+                // Host names are case-insensitive, and either the short or the full name may be listed
+                let options = MatchOptions {
+                    case_sensitive: false,
+                    ..MatchOptions::default()
+                };
+                let short = host.split('.').next().unwrap_or(host);
+                self.only_hosts
+                    .iter()
+                    .filter_map(|pattern| Pattern::new(pattern).ok())
+                    .any(|pattern| pattern.matches_with(host, options) || pattern.matches_with(short, options))
+            });
+        let profile_matches = self.profiles.is_empty()
+            || manager
+                .profile
+                .as_ref()
+                .is_some_and(|profile| self.profiles.contains(profile));
+        host_matches && profile_matches
+    }
+
+    /// Merges the override table for the target OS into the entry
+    fn apply_platform_override(&mut self, target_os: &str) {
+        let platform_override = match target_os {
+            "linux" => self.linux.take(),
+            "macos" => self.macos.take(),
+            "windows" => self.windows.take(),
+            _ => None,
+        };
+        self.linux = None;
+        self.macos = None;
+        self.windows = None;
+        let Some(platform_override) = platform_override else {
+            return;
+        };
+        if let Some(repo) = platform_override.repo {
+            self.repo = repo;
+        }
+        if let Some(exe) = platform_override.exe {
+            self.exe = vec![exe];
+        }
+        if let Some(version_arg) = platform_override.version_arg {
+            self.version_arg = version_arg;
+        }
+        if platform_override.url.is_some() {
+            self.url = platform_override.url;
+        }
+        if platform_override.matching.is_some() {
+            self.matching = platform_override.matching;
+        }
+        if platform_override.matching_regex.is_some() {
+            self.matching_regex = platform_override.matching_regex;
+        }
+    }
+}
+
+/// How a binary is built or fetched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Source {
+    /// Prebuilt release assets from the configured forge or URL
+    #[default]
+    #[serde(rename = "release")]
+    Release,
+    /// A crate published on crates.io, named by `repo`
+    #[serde(rename = "crates-io")]
+    CratesIo,
+}
+
+impl Source {
+    fn is_release(&self) -> bool {
+        *self == Source::Release
+    }
+}
+
+/// C library variant of a Linux build
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Libc {
+    Gnu,
+    Musl,
+}
+
+impl Libc {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Libc::Gnu => "gnu",
+            Libc::Musl => "musl",
+        }
+    }
+}
+
+/// Where a binary's releases are published
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Forge {
+    #[default]
+    GitHub,
+    GitLab,
+    /// Gitea-compatible forges such as Forgejo and Codeberg
+    #[serde(alias = "gitea")]
+    Forgejo,
+}
+
+impl Forge {
+    fn is_github(&self) -> bool {
+        *self == Forge::GitHub
+    }
+
+    /// API root used when an entry sets no `api_base_url`
+    pub fn default_api_base_url(&self) -> &'static str {
+        match self {
+            Forge::GitHub => "https://api.github.com",
+            Forge::GitLab => "https://gitlab.com/api/v4",
+            Forge::Forgejo => "https://codeberg.org/api/v1",
+        }
+    }
+}
+
+/// The `[retry]` table of binaries.toml
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct RetryConfig {
+    /// Tries in total, the first included; 1 disables retries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempts: Option<u32>,
+    /// Seconds before the first retry, doubling with each further one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff: Option<f64>,
+    /// Randomize each wait so parallel installs don't retry in lockstep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter: Option<bool>,
+}
+
+/// Contents of binaries.toml and the files merged into it
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct Config {
+    /// Token for GitHub API requests, used when GITHUB_TOKEN and GH_TOKEN are unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_token: Option<String>,
+    /// Token for GitLab API requests, used when GITLAB_TOKEN is unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gitlab_token: Option<String>,
+    /// Token for Forgejo/Gitea API requests, used when FORGEJO_TOKEN is unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forgejo_token: Option<String>,
+    /// Architecture to download for instead of the host's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_arch: Option<String>,
+    /// OS to download for instead of the host's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_os: Option<String>,
+    /// C library to prefer for Linux assets unless a binary sets its own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub libc: Option<Libc>,
+    /// Proxy URL for all downloads and API requests, used when no proxy environment variable is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// GitHub API root for every GitHub binary without its own `api_base_url`, e.g. a GitHub Enterprise server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_base_url: Option<String>,
+    /// Download bandwidth cap such as "2M", in bytes per second with an optional K, M, or G suffix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_rate: Option<String>,
+    /// Seconds a request may wait for the server to send anything before it fails; 30 when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_timeout: Option<u64>,
+    /// Seconds to wait for a connection to be established; 30 when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<u64>,
+    /// How transient network failures are retried
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryConfig>,
+    /// Download mirrors for every binary without its own `mirrors`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+    /// Remove macOS's quarantine attribute from installed executables so Gatekeeper lets them run; on by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strip_quarantine: Option<bool>,
+    /// Further config files merged after this one, relative to its directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub binaries: Vec<Binary>,
+}
+
+impl Config {
+    /// Merges a later config file: its binaries replace same-named ones, its settings fill unset ones
+    fn merge(&mut self, other: Config) {
+        self.github_token = self.github_token.take().or(other.github_token);
+        self.gitlab_token = self.gitlab_token.take().or(other.gitlab_token);
+        self.forgejo_token = self.forgejo_token.take().or(other.forgejo_token);
+        self.target_arch = self.target_arch.take().or(other.target_arch);
+        self.target_os = self.target_os.take().or(other.target_os);
+        self.libc = self.libc.or(other.libc);
+        self.proxy = self.proxy.take().or(other.proxy);
+        self.api_base_url = self.api_base_url.take().or(other.api_base_url);
+        self.strip_quarantine = self.strip_quarantine.or(other.strip_quarantine);
+        self.limit_rate = self.limit_rate.take().or(other.limit_rate);
+        self.http_timeout = self.http_timeout.or(other.http_timeout);
+        self.connect_timeout = self.connect_timeout.or(other.connect_timeout);
+        self.retry = self.retry.take().or(other.retry);
+        if self.mirrors.is_empty() {
+            self.mirrors = other.mirrors;
+        }
+        for binary in other.binaries {
+            self.binaries.retain(|existing| existing.name != binary.name);
+            self.binaries.push(binary);
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    binaries: Vec<LockedBinary>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LockedBinary {
+    name: String,
+    repo: String,
+    tag: String,
+    /// Release asset the binary was installed from, which `--locked` installs again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    asset: Option<String>,
+    /// Digest of the downloaded asset; crates.io builds and assets only ubi could pick have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+}
+
+/// The asset an install has to come from and the digest it has to match, from bina.lock or the config; installs
+/// return the one they used
+#[derive(Debug, Default)]
+struct AssetPin {
+    name: Option<String>,
+    sha256: Option<String>,
+}
+
+/// Bookkeeping bina keeps under XDG_DATA_HOME
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    /// Active and previous version of each installed binary
+    #[serde(default)]
+    installed: Vec<InstalledBinary>,
+    /// Binaries rolled back by the user, which `update` leaves alone
+    #[serde(default)]
+    held: Vec<HeldBinary>,
+    /// What executables printed when asked for their version, so unchanged ones needn't run again
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    probes: Vec<VersionProbe>,
+}
+
+/// Output of one version probe, valid while the executable keeps its size and modification time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct VersionProbe {
+    path: String,
+    size: u64,
+    /// Nanoseconds since the Unix epoch
+    modified: u64,
+    version_arg: String,
+    /// stdout and stderr, which are parsed again on every use so a changed `version_regex` takes effect
+    output: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InstalledBinary {
+    name: String,
+    /// Tag the executable in XDG_BIN_HOME links to
+    tag: String,
+    /// Tag that was active before the last install or rollback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    previous: Option<String>,
+    #[serde(default)]
+    repo: String,
+    /// Executable placed in the bin directory
+    #[serde(default)]
+    path: String,
+    /// SHA-256 of the active executable
+    #[serde(default)]
+    sha256: String,
+    /// Seconds since the Unix epoch
+    #[serde(default)]
+    installed_at: u64,
+    /// Man pages and completions copied out of the release archive
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extras: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HeldBinary {
+    name: String,
+    /// Tag that was restored
+    tag: String,
+}
+
+impl State {
+    fn is_held(&self, bin_name: &str) -> bool {
+        self.held.iter().any(|held| held.name == bin_name)
+    }
+
+    /// Whether bina placed the executable at `exe_path`, rather than the user or another tool
+    fn manages(&self, bin_name: &str, exe_path: &Path) -> bool {
+        linked_tag(exe_path).is_some()
+            || self
+                .installed
+                .iter()
+                .any(|installed| installed.name == bin_name && Path::new(&installed.path) == exe_path)
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// Details about one binary, as reported by `info`
+#[derive(Debug, Serialize)]
+pub struct BinaryInfo {
+    pub name: String,
+    pub repo: String,
+    pub exe: String,
+    pub install_path: String,
+    pub installed_version: Option<String>,
+    pub latest_tag: Option<String>,
+    pub release_date: Option<String>,
+    pub asset: Option<String>,
+    pub release_url: Option<String>,
+}
+
+/// Where a binary's executable lives and what else on PATH answers to its name, as reported by `which`
+#[derive(Debug, Serialize)]
+pub struct WhichInfo {
+    pub name: String,
+    pub path: String,
+    pub installed: bool,
+    /// Whether the executable links into bina's version store
+    pub managed: bool,
+    pub tag: Option<String>,
+    pub version: Option<String>,
+    /// Executable earlier on PATH that runs instead of this one
+    pub shadowed_by: Option<String>,
+}
+
+/// A binary installed by bina, as listed by `sbom`
+#[derive(Debug, Serialize)]
+pub struct SbomEntry {
+    pub name: String,
+    pub version: String,
+    pub tag: String,
+    /// Repository, or crate name for crates.io builds
+    pub repo: String,
+    /// Web address of the repository or crate
+    pub source_url: String,
+    /// Asset the binary was installed from, when it can be determined
+    pub download_url: Option<String>,
+    /// SHA-256 of the installed executable
+    pub sha256: String,
+}
+
+/// Installation state of a configured binary, as reported by `check`
+#[derive(Debug, Serialize)]
+pub struct BinaryStatus {
+    pub binary: String,
+    pub installed: bool,
+    pub version: Option<String>,
+    pub latest: Option<String>,
+    pub pinned: Option<String>,
+    /// Rolled back and excluded from updates
+    pub held: bool,
+    /// Installed by bina rather than placed there by hand or another tool
+    pub managed: bool,
+    /// Installed but missing the execute permission, so its version can't be read
+    pub not_executable: bool,
+}
+
+impl BinaryStatus {
+    /// Latest version for display, or `-` when it wasn't checked
+    pub fn latest_label(&self) -> &str {
+        match (&self.pinned, &self.latest) {
+            _ if self.held => "held",
+            (Some(_), _) => "pinned",
+            (None, Some(latest)) => latest,
+            (None, None) => "-",
+        }
+    }
+
+    /// Whether an installed version differs from the latest release
+    pub fn is_outdated(&self) -> bool {
+        match (&self.version, &self.latest) {
+            (Some(version), Some(latest)) => {
+                self.pinned.is_none() && !self.held && Version::parse(version).is_outdated_by(&Version::parse(latest))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Configured binaries together with the settings every command shares
+pub struct BinManager {
+    /// Parsed config entries keyed by name, so iteration follows name order
+    pub data: BTreeMap<String, Binary>,
+    pub xdg_bin_home: String,
+    pub regex: Regex,
+    pub github_token: Option<String>,
+    pub gitlab_token: Option<String>,
+    pub forgejo_token: Option<String>,
+    /// Shared view for the download progress of concurrent installs
+    pub progress: MultiProgress,
+    /// Report what would change without touching the filesystem
+    pub dry_run: bool,
+    /// Platform to download for when it differs from the host
+    pub target_os: Option<String>,
+    pub target_arch: Option<String>,
+    /// Tags selected with --tag; empty selects every binary
+    pub tags: Vec<String>,
+    /// Name of this machine, matched against `only_hosts`
+    pub host: Option<String>,
+    /// Profile selected with BINA_PROFILE, which entries listing `profiles` need
+    pub profile: Option<String>,
+    /// Directory of pre-downloaded release assets to install from instead of the network
+    pub offline_assets: Option<PathBuf>,
+    /// Remove the macOS quarantine attribute after installing
+    pub strip_quarantine: bool,
+    /// Stop batch commands at the first binary that fails instead of carrying on with the rest
+    pub fail_fast: bool,
+    /// GitHub API root for requests that aren't about one binary, such as repository search
+    pub github_api_base_url: String,
+    /// Bandwidth cap shared by all concurrent downloads
+    pub rate_limit: Option<RateLimit>,
+    /// How long a request may go without receiving data, which also bounds ubi installs
+    pub http_timeout: Duration,
+    /// How API requests and downloads are retried
+    pub retry: RetryPolicy,
+    /// HTTP client every request goes through, so connections and TLS sessions are reused
+    pub client: Client,
+    /// Proxy from the config that `client` uses; ubi only honours one from the environment
+    pub proxy: Option<String>,
+    /// Version probes by executable path, loaded from the state file on first use
+    probes: Mutex<Option<HashMap<String, VersionProbe>>>,
+}
+
+/// How often and how patiently transient network failures are retried
+pub struct RetryPolicy {
+    /// Tries in total, the first included
+    pub attempts: u32,
+    /// Wait before the first retry, doubling with each further one
+    pub backoff: Duration,
+    /// Wait a random half to all of each delay
+    pub jitter: bool,
+}
+
+/// Longest wait between two tries, however many came before
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+impl RetryPolicy {
+    fn from_config(config: Option<&RetryConfig>) -> Result<Self, BinaError> {
+        let backoff = config.and_then(|retry| retry.backoff).unwrap_or(1.0);
+        Ok(RetryPolicy {
+            attempts: config.and_then(|retry| retry.attempts).unwrap_or(5).max(1),
+            backoff: Duration::try_from_secs_f64(backoff)
+                .map_err(|e| BinaError::ConfigInvalid(format!("Invalid retry backoff {}: {}", backoff, e)))?,
+            jitter: config.and_then(|retry| retry.jitter).unwrap_or(true),
+        })
+    }
+
+    /// Wait before try number `attempt + 1`
+    fn delay(&self, attempt: u32) -> Duration {
+        let delay = self.backoff.saturating_mul(1 << (attempt - 1).min(16)).min(MAX_BACKOFF);
+        if !self.jitter {
+            return delay;
+        }
+        // A freshly keyed hasher is random enough to spread retries without pulling in a crate for it
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        delay.mul_f64(0.5 + random / 2.0)
+    }
+}
+
+/// Sends a request, retrying connection failures, timeouts, and server errors as the manager's policy allows
+pub async fn send_with_retries(
+    request: reqwest::RequestBuilder,
+    manager: &BinManager,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let mut attempt = 1;
+    loop {
+        // Requests with streaming bodies can't be cloned and so get one try
+        let Some(retry) = request.try_clone().filter(|_| attempt < manager.retry.attempts) else {
+            return request.send().await;
+        };
+        let error = match retry.send().await {
+            Ok(response) if response.status().is_server_error() => response.status().to_string(),
+            Err(e) if e.is_connect() || e.is_timeout() || e.is_request() => e.to_string(),
+            result => return result,
+        };
+        let delay = manager.retry.delay(attempt);
+        debug!("Request failed ({}), retrying in {:.1}s", error, delay.as_secs_f64());
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Caps the combined speed of downloads by delaying each chunk until the bandwidth it uses is due
+pub struct RateLimit {
+    bytes_per_second: u64,
+    /// When the bandwidth handed out so far has been used up
+    next: Mutex<Instant>,
+}
+
+impl RateLimit {
+    pub fn new(bytes_per_second: u64) -> Self {
+        RateLimit {
+            bytes_per_second,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits until `len` more bytes fit within the limit
+    async fn take(&self, len: usize) {
+        let wait = {
+            let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            // Idle time doesn't build up credit for a later burst
+            *next = (*next).max(now) + Duration::from_secs_f64(len as f64 / self.bytes_per_second as f64);
+            *next - now
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Parses a rate such as "500K" or "2M" into bytes per second; suffixes count in powers of 1024 like curl's
+pub fn parse_rate(text: &str) -> Result<u64, String> {
+    let split = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.').len();
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' does not start with a number", text))?;
+    let multiplier = match unit.to_ascii_uppercase().as_str() {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        _ => return Err(format!("unknown unit '{}', use K, M, or G", unit)),
+    };
+    let rate = (number * multiplier as f64) as u64;
+    if rate == 0 {
+        return Err(format!("'{}' would stop all downloads", text));
+    }
+    Ok(rate)
+}
+
+/// Parses an age such as 30d into a duration; units are s, m, h, d, and w
+pub fn parse_age(text: &str) -> Result<Duration, String> {
+    let split = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("'{}' does not start with a number", text))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("unknown unit '{}', use s, m, h, d, or w", unit)),
+    };
+    Ok(Duration::from_secs(number * seconds))
+}
+
+impl BinManager {
+    /// OS binaries are downloaded for, the host unless `--target-os` is given
+    pub fn target_os(&self) -> &str {
+        self.target_os.as_deref().unwrap_or(env::consts::OS)
+    }
+
+    /// Architecture binaries are downloaded for, the host unless `--target-arch` is given
+    pub fn target_arch(&self) -> &str {
+        self.target_arch.as_deref().unwrap_or(env::consts::ARCH)
+    }
+
+    /// Whether batch commands should act on a binary given the target platform, host, profile, and --tag filters
+    pub fn selects(&self, binary: &Binary) -> bool {
+        binary.supports_target(self)
+            && binary.applies_here(self)
+            && (self.tags.is_empty() || binary.tags.iter().any(|tag| self.tags.contains(tag)))
+    }
+
+    /// Reads the config entries again after a command changed the file, keeping every other setting
+    pub fn reload_binaries(&mut self) -> Result<(), BinaError> {
+        let target_os = self.target_os().to_string();
+        self.data = configured_binaries(&mut load_config()?, &target_os);
+        Ok(())
+    }
+
+    /// Whether downloads are for another machine and must not be run or linked here
+    pub fn is_cross_target(&self) -> bool {
+        self.target_os() != env::consts::OS || self.target_arch() != env::consts::ARCH
+    }
+}
+
+/// Loads the config and resolves tokens and the target platform into a `BinManager`
+pub fn new_bin_manager(target_os: Option<String>, target_arch: Option<String>) -> Result<BinManager, BinaError> {
+    let mut config = load_config()?;
+    let target_os = target_os.or(config.target_os.take());
+    let data = configured_binaries(&mut config, target_os.as_deref().unwrap_or(env::consts::OS));
+    let target_arch = target_arch.or(config.target_arch);
+    let xdg_bin_home = bin_home()?;
+    // Two or more numeric parts, keeping a pre-release suffix such as -rc.1
+    let regex = Regex::new(r"(\d+(?:\.\d+)+(?:-(?:alpha|beta|rc|pre)\.?\d*)?)").map_err(|_| "Invalid regex")?;
+    let github_token = env::var("GITHUB_TOKEN")
+        .or_else(|_| env::var("GH_TOKEN"))
+        .ok()
+        .filter(|token| !token.is_empty())
+        .or(config.github_token);
+    let gitlab_token = env::var("GITLAB_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+        .or(config.gitlab_token);
+    let forgejo_token = env::var("FORGEJO_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+        .or(config.forgejo_token);
+    let rate_limit = match &config.limit_rate {
+        Some(rate) => {
+            Some(RateLimit::new(parse_rate(rate).map_err(|e| {
+                BinaError::ConfigInvalid(format!("Invalid limit_rate: {}", e))
+            })?))
+        }
+        None => None,
+    };
+    let proxy = match &config.proxy {
+        Some(proxy) => config_proxy(proxy)?.map(|config_proxy| (proxy.clone(), config_proxy)),
+        None => None,
+    };
+    let http_timeout = Duration::from_secs(config.http_timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let connect_timeout = Duration::from_secs(config.connect_timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    // The timeout bounds each read rather than the whole request, so slow but steady downloads finish
+    let mut client = Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(connect_timeout)
+        .read_timeout(http_timeout);
+    if let Some((_, config_proxy)) = &proxy {
+        client = client.proxy(config_proxy.clone());
+    }
+    let client = client.build()?;
+    Ok(BinManager {
+        data,
+        xdg_bin_home,
+        regex,
+        github_token,
+        gitlab_token,
+        forgejo_token,
+        progress: MultiProgress::new(),
+        dry_run: false,
+        target_os,
+        target_arch,
+        tags: vec![],
+        host: host_name(),
+        profile: env::var("BINA_PROFILE").ok().filter(|profile| !profile.is_empty()),
+        offline_assets: None,
+        strip_quarantine: config.strip_quarantine.unwrap_or(true),
+        fail_fast: false,
+        github_api_base_url: config
+            .api_base_url
+            .as_deref()
+            .unwrap_or(Forge::GitHub.default_api_base_url())
+            .trim_end_matches('/')
+            .to_string(),
+        rate_limit,
+        retry: RetryPolicy::from_config(config.retry.as_ref())?,
+        http_timeout,
+        client,
+        proxy: proxy.map(|(proxy, _)| proxy),
+        probes: Mutex::new(None),
+    })
+}
+
+/// Takes the config's entries keyed by name, with platform overrides and global defaults applied
+fn configured_binaries(config: &mut Config, target_os: &str) -> BTreeMap<String, Binary> {
+    std::mem::take(&mut config.binaries)
+        .into_iter()
+        .map(|mut binary| {
+            binary.apply_platform_override(target_os);
+            binary.libc = binary.libc.or(config.libc);
+            if binary.forge.is_github() {
+                binary.api_base_url = binary.api_base_url.take().or(config.api_base_url.clone());
+            }
+            if binary.mirrors.is_empty() {
+                binary.mirrors = config.mirrors.clone();
+            }
+            (binary.name.clone(), binary)
+        })
+        .collect()
+}
+
+/// Name of this machine, from the kernel on Linux and the `hostname` command elsewhere
+fn host_name() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| {
+            let output = Command::new("hostname").output().ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// User agent of every request; crates.io rejects requests without a descriptive one
+const USER_AGENT: &str = "bina (https://github.com/gunungpw/bina)";
+
+/// Seconds bina waits for a connection or for data before giving up on a request
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// How many times the HTTP timeout a whole ubi install may take, since ubi's own client can't be given one
+const UBI_TIMEOUT_FACTOR: u32 = 10;
+
+/// Proxy variables reqwest reads, in both spellings
+const PROXY_VARS: [&str; 6] = [
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+/// The config's proxy, unless the environment already names one that reqwest picks up on its own
+///
+/// The proxy still skips the hosts in NO_PROXY, like one from the environment would.
+fn config_proxy(proxy: &str) -> Result<Option<reqwest::Proxy>, BinaError> {
+    let config_proxy = reqwest::Proxy::all(proxy)
+        .map_err(|e| BinaError::ConfigInvalid(format!("Invalid proxy '{}': {}", proxy, e)))?;
+    if PROXY_VARS
+        .iter()
+        .any(|var| env::var_os(var).is_some_and(|value| !value.is_empty()))
+    {
+        debug!("Ignoring the config proxy in favour of the environment");
+        return Ok(None);
+    }
+    debug!("Using proxy {}", proxy);
+    Ok(Some(config_proxy.no_proxy(reqwest::NoProxy::from_env())))
+}
+
+/// Binaries bina manages even without a config file
+const DEFAULT_BINARIES: [(&str, &str); 14] = [
+    ("nu", "nushell/nushell"),
+    ("uv", "astral-sh/uv"),
+    ("zoxide", "ajeetdsouza/zoxide"),
+    ("jj", "jj-vcs/jj"),
+    ("fzf", "junegunn/fzf"),
+    ("gh", "cli/cli"),
+    ("yazi", "sxyazi/yazi"),
+    ("micro", "zyedidia/micro"),
+    ("lazygit", "jesseduffield/lazygit"),
+    ("eza", "eza-community/eza"),
+    ("bat", "sharkdp/bat"),
+    ("rclone", "rclone/rclone"),
+    ("gix", "GitoxideLabs/gitoxide"),
+    ("kopia", "kopia/kopia"),
+];
+
+/// Release repositories of common tools, used by `init --scan` to recognize executables
+const KNOWN_REPOS: [(&str, &str); 30] = [
+    ("rg", "BurntSushi/ripgrep"),
+    ("fd", "sharkdp/fd"),
+    ("hyperfine", "sharkdp/hyperfine"),
+    ("delta", "dandavison/delta"),
+    ("starship", "starship/starship"),
+    ("just", "casey/just"),
+    ("btm", "ClementTsang/bottom"),
+    ("dust", "bootandy/dust"),
+    ("procs", "dalance/procs"),
+    ("sd", "chmln/sd"),
+    ("tokei", "XAMPPRocky/tokei"),
+    ("hx", "helix-editor/helix"),
+    ("zellij", "zellij-org/zellij"),
+    ("gitui", "gitui-org/gitui"),
+    ("atuin", "atuinsh/atuin"),
+    ("direnv", "direnv/direnv"),
+    ("mise", "jdx/mise"),
+    ("ruff", "astral-sh/ruff"),
+    ("dive", "wagoodman/dive"),
+    ("k9s", "derailed/k9s"),
+    ("jq", "jqlang/jq"),
+    ("yq", "mikefarah/yq"),
+    ("glow", "charmbracelet/glow"),
+    ("gum", "charmbracelet/gum"),
+    ("xh", "ducaale/xh"),
+    ("watchexec", "watchexec/watchexec"),
+    ("lsd", "lsd-rs/lsd"),
+    ("difft", "Wilfred/difftastic"),
+    ("typos", "crate-ci/typos"),
+    ("shellcheck", "koalaman/shellcheck"),
+];
+
+/// Reads binaries.toml with its includes and drop-ins, then adds the built-in binaries
+pub fn load_config() -> Result<Config, BinaError> {
+    // Load from TOML file; if it doesn't exist, only internal data is used
+    let toml_path = config_path();
+    let mut config = read_expanded_config_file(&toml_path)?;
+    for path in included_config_files(&toml_path, &config.include)? {
+        debug!("Merging {}", path.display());
+        config.merge(read_expanded_config_file(&path.to_string_lossy())?);
+    }
+
+    // Add internal default data for binaries the TOML file doesn't override
+    for (name, repo) in DEFAULT_BINARIES {
+        if config.binaries.iter().any(|binary| binary.name == name) {
+            continue;
+        }
+        config.binaries.push(Binary {
+            name: name.to_string(),
+            repo: repo.to_string(),
+            exe: vec![name.to_string()],
+            version_arg: default_version_arg(),
+            ..Default::default()
+        });
+    }
+
+    Ok(config)
+}
+
+/// Files merged into the main config: explicit includes first, then binaries.d/*.toml in name order
+///
+/// A config fetched from a URL has no directory of its own, so it may only include absolute paths and has no drop-ins.
+fn included_config_files(toml_path: &str, include: &[String]) -> Result<Vec<PathBuf>, BinaError> {
+    if let Some(url) = REMOTE_CONFIG.get() {
+        if let Some(relative) = include.iter().find(|path| Path::new(path).is_relative()) {
+            return Err(format!(
+                "{} includes the relative path {}, which a remote config can't resolve",
+                url, relative
+            )
+            .into());
+        }
+        return Ok(include.iter().map(PathBuf::from).collect());
+    }
+    let config_dir = Path::new(toml_path).parent().unwrap_or(Path::new("."));
+    let mut included: Vec<PathBuf> = include.iter().map(|path| config_dir.join(path)).collect();
+    if let Some(missing) = included.iter().find(|path| !path.is_file()) {
+        return Err(BinaError::ConfigNotFound(missing.display().to_string()));
+    }
+    let mut drop_ins: Vec<PathBuf> = fs::read_dir(config_dir.join("binaries.d"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+        .collect();
+    drop_ins.sort();
+    included.extend(drop_ins);
+    Ok(included)
+}
+
+/// Resolves an XDG base directory, falling back to the Windows equivalent or a path under HOME
+fn base_dir(xdg_var: &str, windows_var: &str, home_fallback: &str) -> String {
+    env::var(xdg_var)
+        .or_else(|e| if cfg!(windows) { env::var(windows_var) } else { Err(e) })
+        .unwrap_or_else(|_| {
+            let home = env::var("HOME").expect("HOME environment variable not set");
+            format!("{}/{}", home, home_fallback)
+        })
+}
+
+/// Config file chosen with --config or BINA_CONFIG, set once at startup
+static CONFIG_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Makes `config_path` return `path` instead of the XDG location; only the first call has an effect
+pub fn set_config_path(path: String) {
+    let _ = CONFIG_OVERRIDE.set(path);
+}
+
+/// URL the config was fetched from when --config names one
+static REMOTE_CONFIG: OnceLock<String> = OnceLock::new();
+
+/// Whether a --config value is a URL to fetch rather than a local path
+pub fn is_remote_config(config: &str) -> bool {
+    config.starts_with("https://") || config.starts_with("http://")
+}
+
+/// Fetches the config from `url` and makes `config_path` return its cached copy
+///
+/// The copy is kept under $XDG_DATA_HOME/bina/.remote-config, next to its bina.lock and out of reach of
+/// `cache clean`, and used when the URL can't be reached, so a machine keeps working offline once bootstrapped. With `sha256`, both fresh and cached copies must match it; plain
+/// HTTP URLs require it, since anyone on the path could otherwise rewrite the config.
+pub async fn use_remote_config(url: &str, sha256: Option<&str>) -> Result<(), BinaError> {
+    if url.starts_with("http://") && sha256.is_none() {
+        return Err(format!(
+            "Refusing to fetch the config from {} over plain HTTP without --config-sha256",
+            url
+        )
+        .into());
+    }
+    let url_digest = hex::encode(Sha256::digest(url.as_bytes()));
+    let cache_path = Path::new(&data_dir())
+        .join(".remote-config")
+        .join(&url_digest[..16])
+        .join("binaries.toml");
+    let check_digest = |bytes: &[u8], source: &str| match sha256 {
+        Some(expected) if !hex::encode(Sha256::digest(bytes)).eq_ignore_ascii_case(expected) => {
+            Err(BinaError::VerificationFailed(format!(
+                "Config from {} does not match the expected SHA-256 {}",
+                source, expected
+            )))
+        }
+        _ => Ok(()),
+    };
+
+    let client = Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
+        .read_timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
+        .build()?;
+    debug!("Fetching the config from {}", url);
+    let fetched = async { client.get(url).send().await?.error_for_status()?.bytes().await }.await;
+    match fetched {
+        Ok(bytes) => {
+            check_digest(&bytes, url)?;
+            if let Some(parent) = cache_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&cache_path, &bytes)?;
+            debug!("Cached the config at {}", cache_path.display());
+        }
+        Err(e) if cache_path.is_file() => {
+            warn!("Failed to fetch {} ({}), using the cached copy", url, e);
+            check_digest(&fs::read(&cache_path)?, &cache_path.display().to_string())?;
+        }
+        Err(e) => return Err(BinaError::DownloadFailed(format!("Failed to fetch {}: {}", url, e))),
+    }
+    let _ = REMOTE_CONFIG.set(url.to_string());
+    set_config_path(cache_path.to_string_lossy().into_owned());
+    Ok(())
+}
+
+/// Path of binaries.toml for commands that edit it, which can't change a config fetched from a URL
+pub fn local_config_path() -> Result<String, BinaError> {
+    match REMOTE_CONFIG.get() {
+        Some(url) => Err(format!("The config comes from {}; edit it there instead", url).into()),
+        None => Ok(config_path()),
+    }
+}
+
+/// Path of binaries.toml: the `set_config_path` override, or the XDG config directory
+pub fn config_path() -> String {
+    if let Some(path) = CONFIG_OVERRIDE.get() {
+        return path.clone();
+    }
+    format!(
+        "{}/bina/binaries.toml",
+        base_dir("XDG_CONFIG_HOME", "APPDATA", ".config")
+    )
+}
+
+/// Install directory: XDG_BIN_HOME, or %LOCALAPPDATA%\bina\bin on Windows
+pub fn bin_home() -> Result<String, BinaError> {
+    match env::var("XDG_BIN_HOME") {
+        Ok(xdg_bin_home) => Ok(xdg_bin_home),
+        Err(_) if cfg!(windows) => {
+            let local_app_data =
+                env::var("LOCALAPPDATA").map_err(|_| "Neither XDG_BIN_HOME nor LOCALAPPDATA is set")?;
+            Ok(format!("{}\\bina\\bin", local_app_data))
+        }
+        Err(_) => Err("XDG_BIN_HOME environment variable not set".into()),
+    }
+}
+
+/// File name of an executable on the given OS, e.g. `fzf.exe` on Windows
+fn exe_file_name(exe: &str, os: &str) -> String {
+    if os == "windows" && !exe.ends_with(".exe") {
+        format!("{}.exe", exe)
+    } else {
+        exe.to_string()
+    }
+}
+
+/// Reads one config file as written, without expansion, for editing and writing back
+pub fn read_config_file(toml_path: &str) -> Result<Config, BinaError> {
+    match fs::read_to_string(toml_path) {
+        Ok(toml_str) => Ok(toml::from_str(&toml_str)
+            .map_err(|_| BinaError::ConfigInvalid(format!("Failed to parse binaries.toml from {}", toml_path)))?),
+        Err(_) => Ok(Config::default()),
+    }
+}
+
+/// Reads a config file for use, expanding `~` and environment variables in every string value
+fn read_expanded_config_file(toml_path: &str) -> Result<Config, BinaError> {
+    let Ok(toml_str) = fs::read_to_string(toml_path) else {
+        return Ok(Config::default());
+    };
+    let mut document: toml::Value = toml::from_str(&toml_str)
+        .map_err(|_| BinaError::ConfigInvalid(format!("Failed to parse binaries.toml from {}", toml_path)))?;
+    expand_values(&mut document).map_err(|e| BinaError::ConfigInvalid(format!("{} in {}", e, toml_path)))?;
+    let mut config: Config = document
+        .try_into()
+        .map_err(|_| BinaError::ConfigInvalid(format!("Failed to parse binaries.toml from {}", toml_path)))?;
+    for binary in &mut config.binaries {
+        if binary.exe.is_empty() {
+            binary.exe = vec![binary.name.clone()];
+        }
+    }
+    Ok(config)
+}
+
+fn expand_values(value: &mut toml::Value) -> Result<(), String> {
+    match value {
+        toml::Value::String(text) => *text = expand_env(text)?,
+        toml::Value::Array(values) => values.iter_mut().try_for_each(expand_values)?,
+        toml::Value::Table(table) => table.iter_mut().try_for_each(|(_, value)| expand_values(value))?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Expands a leading `~`, `$VAR`, and `${VAR}`; `$$` is a literal dollar sign
+fn expand_env(text: &str) -> Result<String, String> {
+    let home = || env::var("HOME").map_err(|_| "HOME is not set".to_string());
+    let mut expanded = String::new();
+    let mut rest = match text.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            expanded.push_str(&home()?);
+            rest
+        }
+        _ => text,
+    };
+    while let Some(index) = rest.find('$') {
+        expanded.push_str(&rest[..index]);
+        let after = &rest[index + 1..];
+        let (name, remainder) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced.find('}').ok_or_else(|| format!("Unclosed ${{ in '{}'", text))?;
+            (&braced[..end], &braced[end + 1..])
+        } else if let Some(remainder) = after.strip_prefix('$') {
+            expanded.push('$');
+            rest = remainder;
+            continue;
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+        if name.is_empty() {
+            // A lone `$`, e.g. a regex anchor, stays as written
+            expanded.push('$');
+        } else {
+            expanded.push_str(&env::var(name).map_err(|_| format!("Environment variable {} is not set", name))?);
+        }
+        rest = remainder;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+fn write_config_file(toml_path: &str, config: &Config) -> Result<(), BinaError> {
+    if let Some(parent) = Path::new(toml_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(toml_path, toml::to_string(config)?)?;
+    Ok(())
+}
+
+fn lockfile_path() -> String {
+    Path::new(&config_path())
+        .with_file_name("bina.lock")
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn read_lockfile() -> Result<Lockfile, BinaError> {
+    let lock_path = lockfile_path();
+    match fs::read_to_string(&lock_path) {
+        Ok(lock_str) => Ok(toml::from_str(&lock_str)
+            .map_err(|_| BinaError::ConfigInvalid(format!("Failed to parse {}", lock_path)))?),
+        Err(_) => Ok(Lockfile::default()),
+    }
+}
+
+fn write_lockfile(lockfile: &Lockfile) -> Result<(), BinaError> {
+    let lock_path = lockfile_path();
+    if let Some(parent) = Path::new(&lock_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&lock_path, toml::to_string(lockfile)?)?;
+    Ok(())
+}
+
+fn data_dir() -> String {
+    format!("{}/bina", base_dir("XDG_DATA_HOME", "LOCALAPPDATA", ".local/share"))
+}
+
+/// Downloaded release assets, reused when the same version is installed again
+fn cache_dir() -> String {
+    format!("{}/bina", base_dir("XDG_CACHE_HOME", "TEMP", ".cache"))
+}
+
+/// Cache location of a release asset, keyed by repo, tag, and asset name
+fn cached_asset(binary: &Binary, tag: &str, asset: &str) -> PathBuf {
+    Path::new(&cache_dir()).join(&binary.repo).join(tag).join(asset)
+}
+
+/// Where an asset is downloaded to before it's complete, so an interrupted download can resume
+fn partial_path(cache_path: &Path) -> PathBuf {
+    let mut partial = cache_path.as_os_str().to_owned();
+    partial.push(".part");
+    PathBuf::from(partial)
+}
+
+/// Stores a download in the cache; failing to cache only costs a download later
+fn cache_asset(cache_path: &Path, bytes: &[u8]) {
+    let partial = partial_path(cache_path);
+    let result = fs::create_dir_all(cache_path.parent().unwrap_or(Path::new(".")))
+        .and_then(|_| fs::write(&partial, bytes))
+        .and_then(|_| fs::rename(&partial, cache_path));
+    match result {
+        Ok(()) => debug!("Cached {}", cache_path.display()),
+        Err(e) => debug!("Could not cache {}: {}", cache_path.display(), e),
+    }
+}
+
+/// Files under `dir` with their sizes and modification times
+fn cache_files(dir: &Path, files: &mut Vec<(PathBuf, u64, SystemTime)>) {
+    for entry in fs::read_dir(dir).into_iter().flatten().filter_map(|entry| entry.ok()) {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            cache_files(&entry.path(), files);
+        } else {
+            files.push((entry.path(), metadata.len(), metadata.modified().unwrap_or(UNIX_EPOCH)));
+        }
+    }
+}
+
+/// Byte count for people, e.g. 1.5 GiB
+fn human_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
+
+/// Reports how much disk space cached downloads and metadata take
+pub fn cache_size() -> String {
+    let mut files = vec![];
+    cache_files(Path::new(&cache_dir()), &mut files);
+    let bytes = files.iter().map(|(_, size, _)| size).sum();
+    format!("{} in {} files under {}", human_size(bytes), files.len(), cache_dir())
+}
+
+/// Deletes cached files, or only those last modified more than `older_than` ago
+pub fn clean_cache(older_than: Option<Duration>, dry_run: bool) -> Result<String, BinaError> {
+    let cache_dir = cache_dir();
+    let mut files = vec![];
+    cache_files(Path::new(&cache_dir), &mut files);
+    let cutoff = older_than.and_then(|age| SystemTime::now().checked_sub(age));
+    files.retain(|(_, _, modified)| cutoff.is_none_or(|cutoff| *modified < cutoff));
+
+    let bytes = files.iter().map(|(_, size, _)| size).sum();
+    if dry_run {
+        for (path, _, _) in &files {
+            info!("Would remove {}", path.display());
+        }
+        return Ok(format!("Would free {} from {} files", human_size(bytes), files.len()));
+    }
+    for (path, _, _) in &files {
+        fs::remove_file(path)?;
+        debug!("Removed {}", path.display());
+        // Drop directories left empty, which fails harmlessly on any that aren't
+        for dir in path.ancestors().skip(1).take_while(|dir| *dir != Path::new(&cache_dir)) {
+            if fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+    }
+    Ok(format!("Freed {} from {} files", human_size(bytes), files.len()))
+}
+
+/// Name shared by the systemd service and timer that `install_schedule` writes
+const SCHEDULE_UNIT: &str = "bina-maintenance";
+
+/// Directory systemd loads user units from
+fn systemd_user_dir() -> PathBuf {
+    Path::new(&base_dir("XDG_CONFIG_HOME", "APPDATA", ".config")).join("systemd/user")
+}
+
+fn systemctl(args: &[&str]) -> Result<(), BinaError> {
+    let status = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .status()
+        .map_err(|e| format!("Failed to run systemctl: {}", e))?;
+    if !status.success() {
+        return Err(format!("systemctl --user {} exited with {}", args.join(" "), status).into());
+    }
+    Ok(())
+}
+
+/// Writes a systemd user service and timer that check for new releases, or run `bina update` with `update`,
+/// on the `interval` calendar expression, and enables the timer
+pub fn install_schedule(interval: &str, update: bool, dry_run: bool) -> Result<String, BinaError> {
+    if !cfg!(target_os = "linux") {
+        return Err("Scheduled runs use systemd timers, which are only available on Linux".into());
+    }
+    // systemd-analyze is the authority on calendar syntax; without it the timer itself reports mistakes
+    if let Ok(output) = Command::new("systemd-analyze").args(["calendar", interval]).output()
+        && !output.status.success()
+    {
+        return Err(format!(
+            "'{}' is not a systemd calendar expression, e.g. daily or weekly",
+            interval
+        )
+        .into());
+    }
+
+    let command = if update { "update" } else { "check --latest --notify" };
+    // User services don't inherit the login shell's environment, so carry over what bina needs
+    let service = format!(
+        "[Unit]\nDescription=Run bina {command}\n\n[Service]\nType=oneshot\nEnvironment=\"XDG_BIN_HOME={}\"\nExecStart=\"{}\" --config \"{}\" {command}\n",
+        bin_home()?,
+        env::current_exe()?.display(),
+        // A fetched config is fetched again on every run, so the timer picks up changes to it
+        REMOTE_CONFIG.get().cloned().unwrap_or_else(config_path),
+    );
+    let timer = format!(
+        "[Unit]\nDescription=Run bina {command} {interval}\n\n[Timer]\nOnCalendar={interval}\nPersistent=true\n\n[Install]\nWantedBy=timers.target\n"
+    );
+
+    let unit_dir = systemd_user_dir();
+    let service_path = unit_dir.join(format!("{}.service", SCHEDULE_UNIT));
+    let timer_path = unit_dir.join(format!("{}.timer", SCHEDULE_UNIT));
+    if dry_run {
+        info!("Would write {}:\n{}", service_path.display(), service);
+        info!("Would write {}:\n{}", timer_path.display(), timer);
+        info!("Would enable {}.timer", SCHEDULE_UNIT);
+        return Ok(format!("Would schedule `bina {}` {}", command, interval));
+    }
+    fs::create_dir_all(&unit_dir)?;
+    fs::write(&service_path, service)?;
+    fs::write(&timer_path, timer)?;
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", "--now", &format!("{}.timer", SCHEDULE_UNIT)])?;
+    Ok(format!(
+        "Scheduled `bina {}` {} with {}",
+        command,
+        interval,
+        timer_path.display()
+    ))
+}
+
+/// Disables and deletes the units written by `install_schedule`
+pub fn remove_schedule(dry_run: bool) -> Result<String, BinaError> {
+    let unit_dir = systemd_user_dir();
+    let unit_paths = [
+        unit_dir.join(format!("{}.service", SCHEDULE_UNIT)),
+        unit_dir.join(format!("{}.timer", SCHEDULE_UNIT)),
+    ];
+    if !unit_paths.iter().any(|path| path.exists()) {
+        return Ok("No schedule is installed.".to_string());
+    }
+    if dry_run {
+        info!("Would disable {}.timer", SCHEDULE_UNIT);
+        for path in &unit_paths {
+            info!("Would remove {}", path.display());
+        }
+        return Ok("Would remove the schedule".to_string());
+    }
+    systemctl(&["disable", "--now", &format!("{}.timer", SCHEDULE_UNIT)])?;
+    for path in unit_paths.iter().filter(|path| path.exists()) {
+        fs::remove_file(path)?;
+    }
+    systemctl(&["daemon-reload"])?;
+    Ok("Removed the schedule".to_string())
+}
+
+fn state_path() -> String {
+    format!("{}/state.toml", data_dir())
+}
+
+/// Directory holding every stored version of a binary
+fn binary_store(bin_name: &str) -> PathBuf {
+    Path::new(&data_dir()).join(bin_name)
+}
+
+fn version_dir(bin_name: &str, tag: &str) -> PathBuf {
+    binary_store(bin_name).join(tag)
+}
+
+/// Tags stored for a binary, oldest first
+fn stored_versions(bin_name: &str) -> Vec<String> {
+    let mut versions: Vec<(SystemTime, String)> = fs::read_dir(binary_store(bin_name))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry
+                .file_name()
+                .into_string()
+                .ok()
+                .filter(|name| !name.starts_with('.'))?;
+            let modified = entry.metadata().and_then(|metadata| metadata.modified()).ok()?;
+            Some((modified, name))
+        })
+        .collect();
+    versions.sort();
+    versions.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Tag of the version directory an executable in XDG_BIN_HOME links to
+fn linked_tag(exe_path: &Path) -> Option<String> {
+    let target = fs::read_link(exe_path).ok()?;
+    if !target.starts_with(data_dir()) {
+        return None;
+    }
+    target.parent()?.file_name()?.to_str().map(String::from)
+}
+
+/// Points `exe_path` at a stored version, replacing whatever was there in one step
+fn link_exe(version_exe: &Path, exe_path: &Path) -> Result<(), BinaError> {
+    #[cfg(unix)]
+    {
+        let link_path = exe_path.with_extension("bina-link");
+        if fs::symlink_metadata(&link_path).is_ok() {
+            fs::remove_file(&link_path)?;
+        }
+        std::os::unix::fs::symlink(version_exe, &link_path)?;
+        fs::rename(&link_path, exe_path)?;
+    }
+    #[cfg(not(unix))]
+    fs::copy(version_exe, exe_path)?;
+    Ok(())
+}
+
+/// Links each executable of the binary stored in `version_dir` into the directory of `exe_path`
+fn link_exes(binary: &Binary, version_dir: &Path, exe_path: &Path, manager: &BinManager) -> Result<(), BinaError> {
+    for exe in &binary.exe {
+        let file_name = exe_file_name(exe, manager.target_os());
+        link_exe(&version_dir.join(&file_name), &exe_path.with_file_name(&file_name))?;
+    }
+    Ok(())
+}
+
+/// Where an alias of the executable at `exe_path` is linked
+fn alias_path(exe_path: &Path, alias: &str, manager: &BinManager) -> PathBuf {
+    exe_path.with_file_name(exe_file_name(alias, manager.target_os()))
+}
+
+/// Whether the file at `path` is one bina created as an alias, rather than something else by that name
+fn is_alias_link(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_symlink() || (cfg!(not(unix)) && metadata.is_file()))
+}
+
+/// Links each alias of the binary to its executable, leaving other files by those names alone
+fn link_aliases(binary: &Binary, exe_path: &Path, manager: &BinManager) -> Result<(), BinaError> {
+    for alias in &binary.aliases {
+        let alias_path = alias_path(exe_path, alias, manager);
+        if fs::symlink_metadata(&alias_path).is_ok() && !is_alias_link(&alias_path) {
+            manager.progress.suspend(|| {
+                warn!(
+                    "Not linking alias {}: another file already exists there",
+                    alias_path.display()
+                )
+            });
+            continue;
+        }
+        link_exe(exe_path, &alias_path)?;
+        debug!("Linked alias {} to {}", alias_path.display(), exe_path.display());
+    }
+    Ok(())
+}
+
+fn read_state() -> Result<State, BinaError> {
+    let state_path = state_path();
+    match fs::read_to_string(&state_path) {
+        Ok(state_str) => Ok(toml::from_str(&state_str).map_err(|_| format!("Failed to parse {}", state_path))?),
+        Err(_) => Ok(State::default()),
+    }
+}
+
+fn write_state(state: &State) -> Result<(), BinaError> {
+    let state_path = state_path();
+    if let Some(parent) = Path::new(&state_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&state_path, toml::to_string(state)?)?;
+    Ok(())
+}
+
+fn ensure_bin_directory(xdg_bin_home: &str, dry_run: bool) -> Result<(), BinaError> {
+    if xdg_bin_home.is_empty() {
+        return Err("XDG_BIN_HOME environment variable is not set".into());
+    }
+
+    let path = Path::new(xdg_bin_home);
+    if !path.exists() {
+        if dry_run {
+            info!("Would create directory {}", xdg_bin_home);
+            return Ok(());
+        }
+        info!("Creating directory {}...", xdg_bin_home);
+        fs::create_dir_all(path)?;
+    }
+    Ok(())
+}
+
+/// Symlinks ~/.config to ~/.local/dotfiles
+pub fn link_config_directory(dry_run: bool) -> Result<(), BinaError> {
+    let home = env::var("HOME").map_err(|_| "HOME environment variable not set")?;
+    let config_dir = env::var("XDG_CONFIG_HOME").unwrap_or_else(|_| format!("{}/.config", home));
+    let dotfiles_dir = format!("{}/.local/dotfiles", home);
+
+    if dry_run {
+        info!("Would link {} to {}", config_dir, dotfiles_dir);
+        return Ok(());
+    }
+
+    // Ensure the dotfiles directory exists
+    let dotfiles_path = Path::new(&dotfiles_dir);
+    if !dotfiles_path.exists() {
+        info!("Creating dotfiles directory {}...", dotfiles_dir);
+        fs::create_dir_all(dotfiles_path)?;
+    }
+
+    // Check if .config exists
+    let config_path = Path::new(&config_dir);
+    if !config_path.exists() {
+        info!("Creating .config directory {}...", config_dir);
+        fs::create_dir_all(config_path)?;
+    }
+
+    // Remove existing .config if it's a symlink
+    if config_path.is_symlink() {
+        info!("Removing existing .config symlink...");
+        fs::remove_file(&config_dir)?;
+    }
+
+    // Create symbolic link
+    info!("Creating symbolic link from {} to {}...", config_dir, dotfiles_dir);
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&dotfiles_dir, &config_dir)?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_dir(&dotfiles_dir, &config_dir)?;
+
+    info!("Successfully linked .config to ~/.local/dotfiles");
+    Ok(())
+}
+
+fn forge_token(forge: Forge, manager: &BinManager) -> Option<&str> {
+    match forge {
+        Forge::GitHub => manager.github_token.as_deref(),
+        Forge::GitLab => manager.gitlab_token.as_deref(),
+        Forge::Forgejo => manager.forgejo_token.as_deref(),
+    }
+}
+
+fn api_base_url(binary: &Binary) -> &str {
+    binary
+        .api_base_url
+        .as_deref()
+        .unwrap_or(binary.forge.default_api_base_url())
+        .trim_end_matches('/')
+}
+
+async fn check_latest_crate(client: &Client, crate_name: &str, manager: &BinManager) -> Result<String, BinaError> {
+    let url = format!("https://crates.io/api/v1/crates/{}", crate_name);
+    let request = client.get(&url);
+    let json: Value = send_api_request(request, crate_name, manager).await?;
+    json["crate"]["max_stable_version"]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| BinaError::ReleaseNotFound(crate_name.to_string()))
+}
+
+/// Sends an API request and decodes its JSON, telling rate limits and missing projects apart from other failures
+async fn send_api_request<T: serde::de::DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+    project: &str,
+    manager: &BinManager,
+) -> Result<T, BinaError> {
+    let response = send_with_retries(request, manager).await?;
+    let status = response.status();
+    let exhausted = response
+        .headers()
+        .get("x-ratelimit-remaining")
+        .is_some_and(|remaining| remaining == "0");
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS || (status == reqwest::StatusCode::FORBIDDEN && exhausted) {
+        return Err(BinaError::RateLimited(project.to_string()));
+    }
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Err(BinaError::ReleaseNotFound(project.to_string()));
+    }
+    Ok(response.error_for_status()?.json().await?)
+}
+
+/// A forge release, normalized across GitHub, GitLab, and Forgejo
+#[derive(Debug)]
+struct Release {
+    tag: String,
+    published: Option<String>,
+    url: Option<String>,
+    body: Option<String>,
+    assets: Vec<String>,
+    /// Download URL of each asset, by name
+    asset_urls: HashMap<String, String>,
+    /// GitHub API URL of each asset, which unlike the download URL accepts a token for private repositories
+    asset_api_urls: HashMap<String, String>,
+}
+
+impl Release {
+    fn from_json(forge: Forge, json: &Value) -> Option<Release> {
+        let text = |value: &Value| value.as_str().map(String::from);
+        let (published, url, body, assets, asset_url) = match forge {
+            Forge::GitLab => (
+                &json["released_at"],
+                &json["_links"]["self"],
+                &json["description"],
+                &json["assets"]["links"],
+                "direct_asset_url",
+            ),
+            Forge::GitHub | Forge::Forgejo => (
+                &json["published_at"],
+                &json["html_url"],
+                &json["body"],
+                &json["assets"],
+                "browser_download_url",
+            ),
+        };
+        Some(Release {
+            tag: text(&json["tag_name"])?,
+            published: text(published),
+            url: text(url),
+            body: text(body).filter(|body| !body.trim().is_empty()),
+            assets: assets
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|asset| text(&asset["name"]))
+                .collect(),
+            asset_urls: assets
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|asset| Some((text(&asset["name"])?, text(&asset[asset_url])?)))
+                .collect(),
+            asset_api_urls: match forge {
+                Forge::GitHub => assets
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|asset| Some((text(&asset["name"])?, text(&asset["url"])?)))
+                    .collect(),
+                Forge::GitLab | Forge::Forgejo => HashMap::new(),
+            },
+        })
+    }
+}
+
+/// Builds an authenticated request against the binary's forge API, `path` being relative to the project
+fn forge_request(client: &Client, binary: &Binary, manager: &BinManager, path: &str) -> reqwest::RequestBuilder {
+    let token = forge_token(binary.forge, manager);
+    let base_url = api_base_url(binary);
+    match binary.forge {
+        Forge::GitHub => {
+            let url = format!("{}/repos/{}/{}", base_url, binary.repo, path);
+            let request = client.get(&url).header("Accept", "application/vnd.github.v3+json");
+            match token {
+                Some(token) => request.bearer_auth(token),
+                None => request,
+            }
+        }
+        Forge::GitLab => {
+            // GitLab addresses projects by their URL-encoded full path
+            let url = format!("{}/projects/{}/{}", base_url, binary.repo.replace('/', "%2F"), path);
+            let request = client.get(&url);
+            match token {
+                Some(token) => request.header("PRIVATE-TOKEN", token),
+                None => request,
+            }
+        }
+        Forge::Forgejo => {
+            let url = format!("{}/repos/{}/{}", base_url, binary.repo, path);
+            let request = client.get(&url);
+            match token {
+                Some(token) => request.header("Authorization", format!("token {}", token)),
+                None => request,
+            }
+        }
+    }
+}
+
+/// Adds the forge token to a download from the binary's own forge, so assets of private repositories download
+///
+/// Downloads from any other host go out without it, so a `url` entry can't leak the token.
+fn authorize_download(
+    request: reqwest::RequestBuilder,
+    url: &str,
+    binary: &Binary,
+    manager: &BinManager,
+) -> reqwest::RequestBuilder {
+    let Some(token) = forge_token(binary.forge, manager) else {
+        return request;
+    };
+    let host = |url: &str| reqwest::Url::parse(url).ok()?.host_str().map(str::to_string);
+    let Some(api_host) = host(api_base_url(binary)) else {
+        return request;
+    };
+    // github.com serves the API from api.github.com, while GitHub Enterprise and GitLab use one host for both
+    let web_host = api_host.strip_prefix("api.").unwrap_or(&api_host);
+    if !host(url).is_some_and(|url_host| url_host == api_host || url_host == web_host) {
+        return request;
+    }
+    match binary.forge {
+        // Asset API URLs answer with JSON unless asked for the file itself
+        Forge::GitHub if url.starts_with(api_base_url(binary)) => {
+            request.bearer_auth(token).header("Accept", "application/octet-stream")
+        }
+        Forge::GitHub => request.bearer_auth(token),
+        Forge::GitLab => request.header("PRIVATE-TOKEN", token),
+        Forge::Forgejo => request.header("Authorization", format!("token {}", token)),
+    }
+}
+
+/// Fetches the release for `tag`, or the latest release when no tag is given
+async fn fetch_release(
+    client: &Client,
+    binary: &Binary,
+    manager: &BinManager,
+    tag: Option<&str>,
+) -> Result<Release, BinaError> {
+    // The latest-release endpoints skip pre-releases, which the full listing has newest first
+    if tag.is_none() && binary.prerelease {
+        return fetch_releases(client, binary, manager)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| BinaError::ReleaseNotFound(binary.repo.clone()));
+    }
+    let path = match (binary.forge, tag) {
+        (Forge::GitLab, None) => "releases/permalink/latest".to_string(),
+        (Forge::GitLab, Some(tag)) => format!("releases/{}", tag),
+        (_, None) => "releases/latest".to_string(),
+        (_, Some(tag)) => format!("releases/tags/{}", tag),
+    };
+    debug!("Fetching {} of {} from {}", path, binary.repo, api_base_url(binary));
+    let json: Value = send_api_request(forge_request(client, binary, manager, &path), &binary.repo, manager).await?;
+    Release::from_json(binary.forge, &json).ok_or_else(|| format!("Malformed release data for {}", binary.repo).into())
+}
+
+/// Fetches the most recent releases, newest first
+async fn fetch_releases(client: &Client, binary: &Binary, manager: &BinManager) -> Result<Vec<Release>, BinaError> {
+    let json: Vec<Value> = send_api_request(
+        forge_request(client, binary, manager, "releases"),
+        &binary.repo,
+        manager,
+    )
+    .await?;
+    Ok(json
+        .iter()
+        // Drafts are listed to maintainers but have nothing published yet
+        .filter(|release| release["draft"] != true)
+        .filter_map(|release| Release::from_json(binary.forge, release))
+        .collect())
+}
+
+/// Changelog of every release after `current` up to and including `latest`
+async fn release_notes(
+    client: &Client,
+    binary: &Binary,
+    manager: &BinManager,
+    current: &str,
+    latest: &str,
+) -> Option<String> {
+    if binary.url.is_some() || binary.source == Source::CratesIo {
+        return None;
+    }
+    let releases = match fetch_releases(client, binary, manager).await {
+        Ok(releases) => releases,
+        Err(e) => {
+            debug!("Failed to fetch release notes for {}: {}", binary.repo, e);
+            return None;
+        }
+    };
+    let notes: Vec<String> = releases
+        .into_iter()
+        .map(|release| (parse_version(&release.tag, binary, manager), release))
+        .skip_while(|(version, _)| version.as_deref() != Some(latest))
+        .take_while(|(version, _)| version.as_deref() != Some(current))
+        .map(|(_, release)| {
+            format!(
+                "## {} {}\n\n{}\n",
+                binary.name,
+                release.tag,
+                release.body.as_deref().unwrap_or("No release notes.").trim()
+            )
+        })
+        .collect();
+    (!notes.is_empty()).then(|| notes.join("\n"))
+}
+
+/// Prints text through $PAGER when stdout is a terminal
+fn page(text: &str) {
+    if io::stdout().is_terminal() {
+        let pager = env::var("PAGER").unwrap_or_else(|_| "less -FRX".to_string());
+        let mut words = pager.split_whitespace();
+        if let Some(program) = words.next() {
+            let child = Command::new(program)
+                .args(words)
+                .stdin(std::process::Stdio::piped())
+                .spawn();
+            if let Ok(mut child) = child {
+                if let Some(mut stdin) = child.stdin.take() {
+                    let _ = stdin.write_all(text.as_bytes());
+                }
+                let _ = child.wait();
+                return;
+            }
+        }
+    }
+    println!("{}", text);
+}
+
+async fn check_latest_release(client: &Client, binary: &Binary, manager: &BinManager) -> Result<String, BinaError> {
+    if binary.source == Source::CratesIo {
+        return check_latest_crate(client, &binary.repo, manager).await;
+    }
+    Ok(fetch_release(client, binary, manager, None).await?.tag)
+}
+
+/// Repositories asked about in one GraphQL query, GitHub's limit on nodes per connection
+const GRAPHQL_BATCH_SIZE: usize = 100;
+
+/// GraphQL endpoint next to a GitHub REST API root, e.g. https://ghe.example.com/api/graphql for
+/// https://ghe.example.com/api/v3
+fn graphql_url(api_base_url: &str) -> String {
+    match api_base_url.strip_suffix("/v3") {
+        Some(root) => format!("{}/graphql", root),
+        None => format!("{}/graphql", api_base_url),
+    }
+}
+
+/// Latest release tags of many GitHub binaries by name, fetched with one GraphQL query per hundred repositories
+///
+/// GraphQL needs a token, so without one this returns nothing. Binaries it can't answer for, such as those
+/// tracking pre-releases or on another GitHub server, are left to the REST API.
+async fn batch_latest_tags(binaries: &[&Binary], manager: &BinManager) -> HashMap<String, String> {
+    let mut tags = HashMap::new();
+    let Some(token) = &manager.github_token else {
+        return tags;
+    };
+    let batchable: Vec<&Binary> = binaries
+        .iter()
+        .copied()
+        .filter(|binary| binary.forge.is_github() && binary.source == Source::Release && binary.url.is_none())
+        .filter(|binary| !binary.prerelease && api_base_url(binary) == manager.github_api_base_url)
+        .filter(|binary| binary.repo.split_once('/').is_some())
+        .collect();
+    for chunk in batchable.chunks(GRAPHQL_BATCH_SIZE) {
+        let fields: String = chunk
+            .iter()
+            .enumerate()
+            .filter_map(|(index, binary)| {
+                let (owner, name) = binary.repo.split_once('/')?;
+                // JSON string literals are valid GraphQL ones
+                Some(format!(
+                    "r{}: repository(owner: {}, name: {}) {{ latestRelease {{ tagName }} }} ",
+                    index,
+                    serde_json::to_string(owner).ok()?,
+                    serde_json::to_string(name).ok()?
+                ))
+            })
+            .collect();
+        debug!(
+            "Fetching the latest releases of {} repositories with GraphQL",
+            chunk.len()
+        );
+        let request = manager
+            .client
+            .post(graphql_url(&manager.github_api_base_url))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "query": format!("query {{ {}}}", fields) }));
+        let json: Value = match send_api_request(request, "GraphQL", manager).await {
+            Ok(json) => json,
+            Err(e) => {
+                debug!("Falling back to the REST API after GraphQL failed: {}", e);
+                continue;
+            }
+        };
+        // Missing repositories come back as errors next to the data of the others
+        for (index, binary) in chunk.iter().enumerate() {
+            if let Some(tag) = json["data"][format!("r{}", index)]["latestRelease"]["tagName"].as_str() {
+                tags.insert(binary.name.clone(), tag.to_string());
+            }
+        }
+    }
+    tags
+}
+
+fn spinner_style() -> ProgressStyle {
+    ProgressStyle::with_template("{spinner:.green} {prefix:.bold} {msg}").expect("valid progress template")
+}
+
+fn download_style() -> ProgressStyle {
+    ProgressStyle::with_template("{spinner:.green} {prefix:.bold} [{bar:30}] {bytes}/{total_bytes} {msg}")
+        .expect("valid progress template")
+        .progress_chars("=> ")
+}
+
+fn sha256_file(path: &Path) -> Result<String, BinaError> {
+    let bytes = fs::read(path)?;
+    Ok(hex::encode(Sha256::digest(&bytes)))
+}
+
+/// Installs a binary at `tag_override`, its pinned or locked tag, or the latest release, returning the tag
+pub async fn get_binary(
+    bin_name: &str,
+    manager: &BinManager,
+    locked: bool,
+    tag_override: Option<&str>,
+) -> Result<String, BinaError> {
+    let binary = manager
+        .data
+        .get(bin_name)
+        .ok_or_else(|| BinaError::BinaryNotFound(bin_name.to_string()))?;
+    ensure_bin_directory(&binary_dir(binary, manager), manager.dry_run)?;
+    if !binary.supports_target(manager) {
+        return Err(format!(
+            "Binary '{}' is only available on {}",
+            bin_name,
+            binary.platforms.join(", ")
+        )
+        .into());
+    }
+
+    let bar = manager.progress.add(
+        ProgressBar::new_spinner()
+            .with_style(spinner_style())
+            .with_prefix(bin_name.to_string())
+            .with_message("resolving release")
+            .with_finish(ProgressFinish::AndClear),
+    );
+    bar.enable_steady_tick(Duration::from_millis(100));
+
+    let lockfile = read_lockfile()?;
+    let locked_binary = lockfile
+        .binaries
+        .iter()
+        .find(|locked_binary| locked_binary.name == bin_name);
+
+    // Resolve the tag up front so the lockfile records exactly what was installed
+    let (tag, mut pin) = match (locked, locked_binary, tag_override.or(binary.version.as_deref())) {
+        (true, Some(locked_binary), _) => (
+            locked_binary.tag.clone(),
+            AssetPin {
+                name: locked_binary.asset.clone(),
+                sha256: locked_binary.sha256.clone(),
+            },
+        ),
+        (true, None, _) => return Err(format!("Binary '{}' not found in {}", bin_name, lockfile_path()).into()),
+        (false, _, Some(tag)) => (tag.to_string(), AssetPin::default()),
+        (false, _, None) if manager.offline_assets.is_some() => {
+            (newest_offline_tag(binary, manager)?, AssetPin::default())
+        }
+        // Unversioned URLs always serve whatever the host currently publishes
+        (false, _, None) if binary.url.is_some() => {
+            if binary
+                .url
+                .as_ref()
+                .is_some_and(|url| url.contains("{version}") || url.contains("{tag}"))
+            {
+                return Err(format!("Binary '{}' needs a version to expand its download URL", bin_name).into());
+            }
+            ("latest".to_string(), AssetPin::default())
+        }
+        (false, _, None) => (
+            check_latest_release(&manager.client, binary, manager).await?,
+            AssetPin::default(),
+        ),
+    };
+
+    let exe_path = exe_path(binary, manager);
+    if manager.dry_run {
+        bar.finish_and_clear();
+        manager
+            .progress
+            .suspend(|| info!("Would install {} {} to {}", bin_name, tag, exe_path.display()));
+        return Ok(tag);
+    }
+
+    // An unversioned URL can serve a new build under the same name, so a copy cached by an earlier install is stale
+    if tag == "latest" && binary.url.is_some() {
+        let stale = Path::new(&cache_dir()).join(&binary.repo).join(&tag);
+        if stale.exists() {
+            fs::remove_dir_all(&stale)?;
+            debug!("Dropped the cached download in {}", stale.display());
+        }
+    }
+
+    // The configured checksum describes the pinned release, not an ad-hoc tag or the latest one
+    if pin.sha256.is_none() && tag_override.is_none() && binary.version.is_some() {
+        pin.sha256 = binary.sha256.clone();
+    }
+
+    // Install into a staging directory next to the version store so a bad download never replaces a working binary
+    let staging_dir = binary_store(bin_name).join(".staging");
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir)?;
+    }
+    fs::create_dir_all(&staging_dir)?;
+    bar.set_message(format!("downloading {}", tag));
+    let result = install_staged(binary, &tag, &pin, &staging_dir, &exe_path, manager, &bar).await;
+    if let Err(e) = fs::remove_dir_all(&staging_dir) {
+        warn!("Failed to remove {}: {}", staging_dir.display(), e);
+    }
+    let asset = result?;
+
+    // The lockfile describes this machine, so foreign builds stay out of it
+    if !locked && !manager.is_cross_target() {
+        // Re-read so concurrent installs don't overwrite each other's entries
+        let mut lockfile = read_lockfile()?;
+        lockfile.binaries.retain(|locked_binary| locked_binary.name != bin_name);
+        lockfile.binaries.push(LockedBinary {
+            name: bin_name.to_string(),
+            repo: binary.repo.clone(),
+            tag: tag.clone(),
+            asset: asset.name,
+            sha256: asset.sha256,
+        });
+        lockfile.binaries.sort_by(|a, b| a.name.cmp(&b.name));
+        write_lockfile(&lockfile)?;
+    }
+
+    // Hooks set things up for this machine, which a foreign build can't do
+    if !manager.is_cross_target() {
+        bar.set_message("running post_install");
+        manager.progress.suspend(|| run_post_install(binary, manager))?;
+    }
+
+    bar.finish_and_clear();
+    manager
+        .progress
+        .suspend(|| info!("Successfully downloaded {} {}", bin_name, tag));
+    Ok(tag)
+}
+
+/// Runs the binary's `post_install` commands in order, stopping at the first that fails
+fn run_post_install(binary: &Binary, manager: &BinManager) -> Result<(), BinaError> {
+    let mut paths = vec![PathBuf::from(binary_dir(binary, manager))];
+    paths.extend(env::var_os("PATH").iter().flat_map(env::split_paths));
+    let path = env::join_paths(paths).map_err(|e| format!("Cannot extend PATH for post_install: {}", e))?;
+    for hook in &binary.post_install {
+        debug!("Running post_install of {}: {}", binary.name, hook);
+        let mut command = if cfg!(windows) {
+            let mut command = Command::new("cmd");
+            command.arg("/C").arg(hook);
+            command
+        } else {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(hook);
+            command
+        };
+        let output = command.env("PATH", &path).output()?;
+        for line in String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .chain(String::from_utf8_lossy(&output.stderr).lines())
+        {
+            debug!("{}: {}", binary.name, line);
+        }
+        if !output.status.success() {
+            return Err(format!(
+                "post_install `{}` of {} exited with {}; rerun with --verbose to see its output",
+                hook, binary.name, output.status
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Runs a configured binary with `args`, installing it first when it's missing
+///
+/// On Unix bina replaces itself with the binary, so this only returns on failure; elsewhere it
+/// waits for the binary and returns its exit code.
+pub async fn run_binary(bin_name: &str, args: &[String], manager: &BinManager) -> Result<i32, BinaError> {
+    if manager.is_cross_target() {
+        return Err("Cannot run a binary built for another target".into());
+    }
+    let binary = manager
+        .data
+        .get(bin_name)
+        .ok_or_else(|| BinaError::BinaryNotFound(bin_name.to_string()))?;
+    let exe_path = exe_path(binary, manager);
+    if !exe_path.exists() {
+        get_binary(bin_name, manager, false, None).await?;
+    }
+    if manager.dry_run {
+        info!("Would run {} {}", exe_path.display(), args.join(" "));
+        return Ok(0);
+    }
+
+    let mut command = Command::new(&exe_path);
+    command.args(args);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let e = command.exec();
+        Err(format!("Failed to run {}: {}", exe_path.display(), e).into())
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command
+            .status()
+            .map_err(|e| format!("Failed to run {}: {}", exe_path.display(), e))?;
+        Ok(status.code().unwrap_or(1))
+    }
+}
+
+/// Asks a question on stdin, returning `default` for an empty answer
+pub fn prompt(message: &str, default: &str) -> Result<String, BinaError> {
+    print!("{} [{}]: ", message, default);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default } else { answer }.to_string())
+}
+
+/// Appends a GitHub repository to binaries.toml, returning the name of the new entry
+pub fn add_binary(
+    repo: &str,
+    name: Option<String>,
+    exe: Option<String>,
+    version_arg: Option<String>,
+    dry_run: bool,
+) -> Result<String, BinaError> {
+    let repo_name = match repo.split_once('/') {
+        Some((owner, repo_name)) if !owner.is_empty() && !repo_name.is_empty() && !repo_name.contains('/') => repo_name,
+        _ => return Err(format!("Repository '{}' is not in owner/name form", repo).into()),
+    };
+    let name = name.unwrap_or_else(|| repo_name.to_string());
+    let exe = exe.unwrap_or_else(|| name.clone());
+    let version_arg = match version_arg {
+        Some(version_arg) => version_arg,
+        None => prompt(&format!("Version argument for {}", exe), DEFAULT_VERSION_ARG)?,
+    };
+
+    let toml_path = local_config_path()?;
+    let mut config = read_config_file(&toml_path)?;
+    if config.binaries.iter().any(|binary| binary.name == name) {
+        return Err(format!("Binary '{}' already exists in {}", name, toml_path).into());
+    }
+
+    if dry_run {
+        info!("Would add {} ({}) to {}", name, repo, toml_path);
+        return Ok(name);
+    }
+    config.binaries.push(Binary {
+        name: name.clone(),
+        repo: repo.to_string(),
+        exe: if exe == name { vec![] } else { vec![exe] },
+        version_arg,
+        ..Default::default()
+    });
+    write_config_file(&toml_path, &config)?;
+    info!("Added {} to {}", name, toml_path);
+    Ok(name)
+}
+
+/// A repository returned by the GitHub search API
+#[derive(Serialize)]
+pub struct SearchResult {
+    pub repo: String,
+    pub stars: u64,
+    pub description: Option<String>,
+}
+
+/// Finds GitHub repositories matching `query`, most relevant first
+pub async fn search_repositories(
+    query: &str,
+    limit: usize,
+    manager: &BinManager,
+) -> Result<Vec<SearchResult>, BinaError> {
+    let mut request = manager
+        .client
+        .get(format!("{}/search/repositories", manager.github_api_base_url))
+        .query(&[("q", query), ("per_page", &limit.to_string())])
+        .header("Accept", "application/vnd.github.v3+json");
+    if let Some(token) = &manager.github_token {
+        request = request.bearer_auth(token);
+    }
+    let json: Value = send_with_retries(request, manager)
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(json["items"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            Some(SearchResult {
+                repo: item["full_name"].as_str()?.to_string(),
+                stars: item["stargazers_count"].as_u64().unwrap_or(0),
+                description: item["description"].as_str().map(String::from),
+            })
+        })
+        .collect())
+}
+
+/// Writes a starter binaries.toml, optionally listing recognized executables already in XDG_BIN_HOME
+pub fn init_config(scan: bool, force: bool, dry_run: bool) -> Result<String, BinaError> {
+    let toml_path = local_config_path()?;
+    if Path::new(&toml_path).exists() && !force {
+        return Err(format!("{} already exists, pass --force to overwrite it", toml_path).into());
+    }
+
+    let mut config = Config::default();
+    if scan {
+        let bin_dir = bin_home()?;
+        let mut unknown = vec![];
+        for file_name in installed_binaries(&bin_dir) {
+            let exe = file_name.strip_suffix(".exe").unwrap_or(&file_name);
+            // Built-in defaults are always managed, so they need no entry
+            if DEFAULT_BINARIES.iter().any(|(name, _)| *name == exe) {
+                continue;
+            }
+            match KNOWN_REPOS.iter().find(|(name, _)| *name == exe) {
+                Some((name, repo)) => config.binaries.push(Binary {
+                    name: name.to_string(),
+                    repo: repo.to_string(),
+                    version_arg: default_version_arg(),
+                    ..Default::default()
+                }),
+                None if Path::new(&bin_dir).join(&file_name).is_file() => unknown.push(file_name),
+                None => {}
+            }
+        }
+        config.binaries.sort_by(|a, b| a.name.cmp(&b.name));
+        if !unknown.is_empty() {
+            unknown.sort();
+            warn!(
+                "No known repository for {}; add them with `bina add owner/repo`",
+                unknown.join(", ")
+            );
+        }
+    }
+
+    if dry_run {
+        print!("{}", toml::to_string(&config)?);
+        return Ok(format!(
+            "Would write {} with {} binaries",
+            toml_path,
+            config.binaries.len()
+        ));
+    }
+    write_config_file(&toml_path, &config)?;
+    Ok(format!("Wrote {} with {} binaries", toml_path, config.binaries.len()))
+}
+
+/// Looks up a tool by executable or repository name among the built-in and well-known repositories
+fn known_binary(tool: &str) -> Option<(&'static str, &'static str)> {
+    DEFAULT_BINARIES
+        .iter()
+        .chain(&KNOWN_REPOS)
+        .copied()
+        .find(|(exe, repo)| *exe == tool || repo.rsplit('/').next() == Some(tool))
+}
+
+/// Converts one tool entry into a binary, pinning its version unless it tracks the latest release
+fn imported_binary(tool: &str, version: Option<&str>, exe: Option<&str>) -> Option<Binary> {
+    let (backend, name) = tool.split_once(':').unwrap_or(("", tool));
+    let mut binary = match backend {
+        "cargo" => Binary {
+            name: name.to_string(),
+            repo: name.to_string(),
+            source: Source::CratesIo,
+            ..Default::default()
+        },
+        "ubi" | "github" | "aqua" if name.contains('/') => Binary {
+            name: name.rsplit('/').next().unwrap_or(name).to_string(),
+            repo: name.to_string(),
+            ..Default::default()
+        },
+        "" | "asdf" => {
+            let (exe, repo) = known_binary(name)?;
+            Binary {
+                name: exe.to_string(),
+                repo: repo.to_string(),
+                ..Default::default()
+            }
+        }
+        _ => return None,
+    };
+    binary.exe = exe
+        .filter(|exe| *exe != binary.name)
+        .map(|exe| vec![exe.to_string()])
+        .unwrap_or_default();
+    binary.version_arg = default_version_arg();
+    binary.version = version
+        .filter(|version| !matches!(*version, "latest" | "stable" | "system"))
+        .map(String::from);
+    Some(binary)
+}
+
+/// One tool listed in a mise or asdf config
+struct ToolEntry {
+    tool: String,
+    version: Option<String>,
+    exe: Option<String>,
+}
+
+/// Reads the tools of a mise `[tools]` table or an asdf .tool-versions file
+fn read_tool_list(format: ImportFormat, path: &str) -> Result<Vec<ToolEntry>, BinaError> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let tools = match format {
+        ImportFormat::Mise => {
+            let document: toml::Table =
+                toml::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+            let text = |value: &toml::Value| value.as_str().map(String::from);
+            document
+                .get("tools")
+                .and_then(|tools| tools.as_table())
+                .into_iter()
+                .flatten()
+                .map(|(tool, spec)| {
+                    // A tool maps to a version, a list of versions, or a table of options
+                    let spec = spec.as_array().and_then(|versions| versions.first()).unwrap_or(spec);
+                    let (version, exe) = match spec.as_table() {
+                        Some(options) => (options.get("version").and_then(text), options.get("exe").and_then(text)),
+                        None => (text(spec), None),
+                    };
+                    ToolEntry {
+                        tool: tool.clone(),
+                        version,
+                        exe,
+                    }
+                })
+                .collect()
+        }
+        ImportFormat::Asdf => contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                Some(ToolEntry {
+                    tool: words.next()?.to_string(),
+                    version: words.next().map(String::from),
+                    exe: None,
+                })
+            })
+            .collect(),
+        ImportFormat::Bina => unreachable!("manifests are read by read_manifest"),
+    };
+    Ok(tools)
+}
+
+fn read_manifest(path: &str) -> Result<Config, BinaError> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let manifest = if path.ends_with(".json") {
+        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path, e))?
+    } else {
+        toml::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path, e))?
+    };
+    Ok(manifest)
+}
+
+/// Every configured binary, pinned to the tag currently installed, without any tokens
+pub fn export_manifest() -> Result<Config, BinaError> {
+    let config = load_config()?;
+    let state = read_state()?;
+    let mut binaries = config.binaries;
+    for binary in &mut binaries {
+        if let Some(installed) = state.installed.iter().find(|installed| installed.name == binary.name) {
+            binary.version = Some(installed.tag.clone());
+        }
+        if binary.exe == [binary.name.as_str()] {
+            binary.exe.clear();
+        }
+    }
+    binaries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Config {
+        libc: config.libc,
+        binaries,
+        ..Default::default()
+    })
+}
+
+/// Adds the tools listed in a manifest, mise, or asdf file to binaries.toml
+pub fn import_binaries(format: ImportFormat, path: &str, dry_run: bool) -> Result<String, BinaError> {
+    let toml_path = local_config_path()?;
+    let mut config = read_config_file(&toml_path)?;
+
+    let mut skipped = vec![];
+    let binaries = match format {
+        ImportFormat::Bina => read_manifest(path)?.binaries,
+        format => read_tool_list(format, path)?
+            .into_iter()
+            .filter_map(|entry| {
+                let binary = imported_binary(&entry.tool, entry.version.as_deref(), entry.exe.as_deref());
+                if binary.is_none() {
+                    skipped.push(entry.tool);
+                }
+                binary
+            })
+            .collect(),
+    };
+
+    let mut imported = vec![];
+    for binary in binaries {
+        if config.binaries.iter().any(|existing| existing.name == binary.name) {
+            debug!("{} is already in {}", binary.name, toml_path);
+            continue;
+        }
+        imported.push(binary.name.clone());
+        config.binaries.push(binary);
+    }
+    if !skipped.is_empty() {
+        warn!(
+            "Skipped tools without a known GitHub repository: {}",
+            skipped.join(", ")
+        );
+    }
+
+    if imported.is_empty() {
+        return Ok("Nothing to import.".to_string());
+    }
+    if !dry_run {
+        write_config_file(&toml_path, &config)?;
+    }
+    Ok(format!(
+        "{} {} binaries into {}:\n  {}",
+        if dry_run { "Would import" } else { "Imported" },
+        imported.len(),
+        toml_path,
+        imported.join("\n  ")
+    ))
+}
+
+/// Deletes an installed binary and its stored versions, and with `purge` its config entry
+pub fn remove_binary(bin_name: &str, purge: bool, manager: &BinManager) -> Result<(), BinaError> {
+    let binary = manager
+        .data
+        .get(bin_name)
+        .ok_or_else(|| BinaError::BinaryNotFound(bin_name.to_string()))?;
+    // Fail before deleting anything when the config can't be edited
+    let toml_path = if purge { Some(local_config_path()?) } else { None };
+
+    let exe_path = exe_path(binary, manager);
+    if exe_path.exists() && !read_state()?.manages(bin_name, &exe_path) {
+        warn!(
+            "Leaving {} in place: bina did not install it, delete it by hand if it should go",
+            exe_path.display()
+        );
+    } else if exe_path.exists() && manager.dry_run {
+        info!("Would remove {}", exe_path.display());
+    } else if exe_path.exists() {
+        fs::remove_file(&exe_path)?;
+        info!("Removed {}", exe_path.display());
+        for exe in binary.exe.iter().skip(1) {
+            let other_exe = exe_path.with_file_name(exe_file_name(exe, manager.target_os()));
+            if fs::symlink_metadata(&other_exe).is_ok() {
+                fs::remove_file(&other_exe)?;
+                info!("Removed {}", other_exe.display());
+            }
+        }
+        for alias in &binary.aliases {
+            let alias_path = alias_path(&exe_path, alias, manager);
+            if is_alias_link(&alias_path) {
+                fs::remove_file(&alias_path)?;
+                debug!("Removed alias {}", alias_path.display());
+            }
+        }
+    } else {
+        warn!("{} is not installed", bin_name);
+    }
+
+    let extras = read_state()?
+        .installed
+        .into_iter()
+        .find(|installed| installed.name == bin_name)
+        .map(|installed| installed.extras)
+        .unwrap_or_default();
+    let store = binary_store(bin_name);
+    if store.exists() && manager.dry_run {
+        info!("Would remove stored versions in {}", store.display());
+        for file in &extras {
+            info!("Would remove {}", file);
+        }
+    } else if store.exists() {
+        fs::remove_dir_all(&store)?;
+        remove_extras(&extras);
+        let mut state = read_state()?;
+        state.installed.retain(|installed| installed.name != bin_name);
+        state.held.retain(|held| held.name != bin_name);
+        write_state(&state)?;
+        debug!("Removed stored versions in {}", store.display());
+    }
+
+    if let Some(toml_path) = toml_path {
+        let mut config = read_config_file(&toml_path)?;
+
+        let before = config.binaries.len();
+        config.binaries.retain(|binary| binary.name != bin_name);
+        if config.binaries.len() == before {
+            warn!("{} has no entry in {}", bin_name, toml_path);
+        } else if manager.dry_run {
+            info!("Would remove {} from {}", bin_name, toml_path);
+        } else {
+            write_config_file(&toml_path, &config)?;
+            info!("Removed {} from {}", bin_name, toml_path);
+        }
+    }
+    Ok(())
+}
+
+/// Relinks a binary to its previous version, or to `to` when given
+pub fn rollback_binary(bin_name: &str, to: Option<&str>, manager: &BinManager) -> Result<(), BinaError> {
+    let binary = manager
+        .data
+        .get(bin_name)
+        .ok_or_else(|| BinaError::BinaryNotFound(bin_name.to_string()))?;
+
+    let mut state = read_state()?;
+    let current = state.installed.iter().find(|installed| installed.name == bin_name);
+    let tag = match (to, current.and_then(|installed| installed.previous.as_deref())) {
+        (Some(tag), _) | (None, Some(tag)) => tag.to_string(),
+        (None, None) => return Err(format!("No previous version of {} to roll back to", bin_name).into()),
+    };
+    let version_exe = version_dir(bin_name, &tag).join(exe_file_name(binary.main_exe(), manager.target_os()));
+    if !version_exe.exists() {
+        return Err(format!(
+            "{} {} is not stored, available versions: {}",
+            bin_name,
+            tag,
+            stored_versions(bin_name).join(", ")
+        )
+        .into());
+    }
+
+    let exe_path = exe_path(binary, manager);
+    if manager.dry_run {
+        info!("Would link {} to {}", exe_path.display(), version_exe.display());
+        return Ok(());
+    }
+    link_exes(binary, &version_dir(bin_name, &tag), &exe_path, manager)?;
+    link_aliases(binary, &exe_path, manager)?;
+
+    // Remember the version we left so a second rollback returns to it
+    let previous = current
+        .map(|installed| installed.tag.clone())
+        .filter(|previous| *previous != tag);
+    let extras = current.map(|installed| installed.extras.clone()).unwrap_or_default();
+    state.installed.retain(|installed| installed.name != bin_name);
+    state.installed.push(InstalledBinary {
+        name: bin_name.to_string(),
+        tag: tag.clone(),
+        previous,
+        repo: binary.repo.clone(),
+        path: exe_path.display().to_string(),
+        sha256: sha256_file(&version_exe)?,
+        installed_at: unix_time(),
+        extras,
+    });
+    state.installed.sort_by(|a, b| a.name.cmp(&b.name));
+    state.held.retain(|held| held.name != bin_name);
+    state.held.push(HeldBinary {
+        name: bin_name.to_string(),
+        tag: tag.clone(),
+    });
+    state.held.sort_by(|a, b| a.name.cmp(&b.name));
+    write_state(&state)?;
+
+    info!("Rolled back {} to {}", bin_name, tag);
+    Ok(())
+}
+
+/// Drops a rollback hold once the user installs the binary explicitly again
+pub fn release_hold(bin_name: &str) -> Result<(), BinaError> {
+    let mut state = read_state()?;
+    let before = state.held.len();
+    state.held.retain(|held| held.name != bin_name);
+    if state.held.len() != before {
+        write_state(&state)?;
+        debug!("Released rollback hold on {}", bin_name);
+    }
+    Ok(())
+}
+
+/// Installs into `staging_dir` from the asset `pin` allows, verifies the download, stores the executable by tag, and
+/// links `exe_path` to it, returning the asset it came from
+async fn install_staged(
+    binary: &Binary,
+    tag: &str,
+    pin: &AssetPin,
+    staging_dir: &Path,
+    exe_path: &Path,
+    manager: &BinManager,
+    bar: &ProgressBar,
+) -> Result<AssetPin, BinaError> {
+    // Signatures cover the release asset, which only bina's own URL downloads see
+    if binary.signature_pattern.is_some() && (binary.url.is_none() || binary.source == Source::CratesIo) {
+        return Err(format!(
+            "Binary '{}' sets signature_pattern, which requires a url source",
+            binary.name
+        )
+        .into());
+    }
+
+    // Attestations are GitHub's, and cover release assets rather than local builds
+    if binary.require_attestation && (binary.forge != Forge::GitHub || binary.source == Source::CratesIo) {
+        return Err(format!(
+            "Binary '{}' sets require_attestation, which needs a GitHub release",
+            binary.name
+        )
+        .into());
+    }
+
+    if binary.cosign_identity.is_some() && binary.source == Source::CratesIo {
+        return Err(format!(
+            "Binary '{}' sets cosign_identity, but crates.io builds have no signed asset",
+            binary.name
+        )
+        .into());
+    }
+
+    if pin.sha256.is_some() && binary.source == Source::CratesIo {
+        return Err(format!(
+            "Binary '{}' sets sha256, but crates.io builds have no asset to check",
+            binary.name
+        )
+        .into());
+    }
+
+    if manager.is_cross_target() && binary.source == Source::CratesIo {
+        return Err(format!(
+            "Binary '{}' is built from crates.io and cannot target another platform",
+            binary.name
+        )
+        .into());
+    }
+
+    let asset = match binary.source {
+        Source::CratesIo if manager.offline_assets.is_some() => {
+            return Err(format!(
+                "Binary '{}' is built from crates.io and cannot be installed offline",
+                binary.name
+            )
+            .into());
+        }
+        Source::CratesIo => {
+            manager
+                .progress
+                .suspend(|| install_from_crates_io(binary, tag, staging_dir))?;
+            AssetPin::default()
+        }
+        Source::Release => install_asset(binary, tag, pin, staging_dir, manager, bar).await?,
+    };
+
+    let staged_exe = staging_dir.join(exe_file_name(binary.main_exe(), manager.target_os()));
+    let sha256 = sha256_file(&staged_exe)?;
+
+    // A foreign build can't run here, and a link into this machine's store is useless elsewhere
+    if manager.is_cross_target() {
+        for exe in &binary.exe {
+            let file_name = exe_file_name(exe, manager.target_os());
+            fs::rename(staging_dir.join(&file_name), exe_path.with_file_name(&file_name))?;
+            set_exe_mode(&exe_path.with_file_name(&file_name), binary)?;
+        }
+        return Ok(asset);
+    }
+
+    // Every version gets its own directory so switching versions is just relinking
+    let version_dir = version_dir(&binary.name, tag);
+    if version_dir.exists() {
+        fs::remove_dir_all(&version_dir)?;
+    }
+    fs::create_dir_all(&version_dir)?;
+    for exe in &binary.exe {
+        let file_name = exe_file_name(exe, manager.target_os());
+        fs::rename(staging_dir.join(&file_name), version_dir.join(&file_name))?;
+        // Archives don't always carry the execute bit, and ubi, cargo, and the cache copy each differ
+        set_exe_mode(&version_dir.join(&file_name), binary)?;
+        if cfg!(target_os = "macos") && manager.strip_quarantine {
+            strip_quarantine(&version_dir.join(&file_name));
+        }
+    }
+    let version_exe = version_dir.join(exe_file_name(binary.main_exe(), manager.target_os()));
+    if let Err(e) = smoke_test(&version_exe, &binary.version_arg) {
+        fs::remove_dir_all(&version_dir)?;
+        return Err(format!("{} {} failed its smoke test: {}", binary.name, tag, e).into());
+    }
+    debug!("{} passed its smoke test", version_exe.display());
+    link_exes(binary, &version_dir, exe_path, manager)?;
+    link_aliases(binary, exe_path, manager)?;
+    let extras = if binary.man_pages || binary.completions {
+        install_extras(binary, tag, asset.name.as_deref(), manager, bar)
+            .await
+            .unwrap_or_else(|e| {
+                manager
+                    .progress
+                    .suspend(|| warn!("Could not install man pages or completions of {}: {}", binary.name, e));
+                vec![]
+            })
+    } else {
+        vec![]
+    };
+
+    // Re-read so concurrent installs don't overwrite each other's entries
+    let mut state = read_state()?;
+    let previous = state
+        .installed
+        .iter()
+        .find(|installed| installed.name == binary.name)
+        .and_then(|installed| {
+            if installed.tag == tag {
+                installed.previous.clone()
+            } else {
+                Some(installed.tag.clone())
+            }
+        });
+    // Drop the extras an earlier release shipped and this one no longer does
+    if let Some(installed) = state.installed.iter().find(|installed| installed.name == binary.name) {
+        let stale: Vec<String> = installed
+            .extras
+            .iter()
+            .filter(|file| !extras.contains(file))
+            .cloned()
+            .collect();
+        remove_extras(&stale);
+    }
+    state.installed.retain(|installed| installed.name != binary.name);
+    state.installed.push(InstalledBinary {
+        name: binary.name.clone(),
+        tag: tag.to_string(),
+        previous,
+        repo: binary.repo.clone(),
+        path: exe_path.display().to_string(),
+        sha256,
+        installed_at: unix_time(),
+        extras,
+    });
+    state.installed.sort_by(|a, b| a.name.cmp(&b.name));
+    write_state(&state)?;
+    Ok(asset)
+}
+
+/// Removes the attribute that makes Gatekeeper block a downloaded executable on first run
+fn strip_quarantine(path: &Path) {
+    match Command::new("xattr")
+        .args(["-d", "com.apple.quarantine"])
+        .arg(path)
+        .output()
+    {
+        Ok(output) if output.status.success() => debug!("Removed the quarantine attribute of {}", path.display()),
+        // Most often the file simply was never quarantined
+        Ok(output) => debug!(
+            "xattr left {} as it was: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => debug!("Failed to run xattr on {}: {}", path.display(), e),
+    }
+}
+
+/// Where a Sigstore bundle is looked for when an entry sets no `cosign_bundle`
+const DEFAULT_COSIGN_BUNDLE: &str = "{url}.sigstore.json";
+
+/// Issuer of the certificates GitHub Actions workflows sign with
+const GITHUB_ACTIONS_ISSUER: &str = "https://token.actions.githubusercontent.com";
+
+/// Checks a keyless cosign signature of the asset with `cosign verify-blob`
+fn verify_cosign(asset_path: &Path, bundle_path: &Path, identity: &str, binary: &Binary) -> Result<(), BinaError> {
+    let mut command = Command::new("cosign");
+    command
+        .arg("verify-blob")
+        .arg(asset_path)
+        .arg("--bundle")
+        .arg(bundle_path)
+        .args(["--certificate-identity-regexp", identity])
+        .args([
+            "--certificate-oidc-issuer",
+            binary.cosign_issuer.as_deref().unwrap_or(GITHUB_ACTIONS_ISSUER),
+        ]);
+    // Bundles in the Sigstore format, unlike cosign's older JSON bundles, have to be announced
+    if bundle_path.to_string_lossy().ends_with(".sigstore.json") {
+        command.arg("--new-bundle-format");
+    }
+    let output = command.output().map_err(|e| {
+        BinaError::VerificationFailed(format!(
+            "{} requires a cosign signature, but cosign could not run: {}",
+            binary.name, e
+        ))
+    })?;
+    if !output.status.success() {
+        return Err(BinaError::VerificationFailed(format!(
+            "cosign verification failed for {}: {}",
+            binary.name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the asset's GitHub artifact attestation with `gh attestation verify`, which does the Sigstore work
+fn verify_attestation(asset_path: &Path, binary: &Binary, manager: &BinManager) -> Result<(), BinaError> {
+    let mut command = Command::new("gh");
+    command
+        .args(["attestation", "verify"])
+        .arg(asset_path)
+        .args(["--repo", &binary.repo]);
+    if let Some(host) = reqwest::Url::parse(api_base_url(binary))
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .filter(|host| host != "api.github.com")
+    {
+        command.args(["--hostname", &host]);
+    }
+    if let Some(token) = &manager.github_token {
+        command.env("GH_TOKEN", token);
+    }
+    let output = command.output().map_err(|e| {
+        BinaError::VerificationFailed(format!(
+            "{} requires an attestation, but the GitHub CLI (gh) could not run: {}",
+            binary.name, e
+        ))
+    })?;
+    if !output.status.success() {
+        return Err(BinaError::VerificationFailed(format!(
+            "Attestation verification failed for {}: {}",
+            binary.name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Runs the freshly installed executable with its version argument
+fn smoke_test(exe_path: &Path, version_arg: &str) -> Result<(), BinaError> {
+    let output = Command::new(exe_path).args(version_arg.split_whitespace()).output()?;
+    if !output.status.success() {
+        return Err(format!("`{} {}` exited with {}", exe_path.display(), version_arg, output.status).into());
+    }
+    Ok(())
+}
+
+/// Rust target triple for an OS and architecture, e.g. aarch64-unknown-linux-gnu
+fn target_triple(os: &str, arch: &str, libc: Option<Libc>) -> String {
+    match os {
+        "linux" => format!("{}-unknown-linux-{}", arch, libc.unwrap_or(Libc::Gnu).as_str()),
+        "macos" => format!("{}-apple-darwin", arch),
+        "windows" => format!("{}-pc-windows-msvc", arch),
+        os => format!("{}-unknown-{}", arch, os),
+    }
+}
+
+/// Installs each of the binary's executables with ubi, from `asset` when bina already picked one
+async fn install_with_ubi(
+    binary: &Binary,
+    tag: &str,
+    asset: Option<&str>,
+    install_dir: &Path,
+    manager: &BinManager,
+) -> Result<(), BinaError> {
+    for exe in &binary.exe {
+        install_exe_with_ubi(binary, exe, tag, asset, install_dir, manager).await?;
+    }
+    Ok(())
+}
+
+async fn install_exe_with_ubi(
+    binary: &Binary,
+    exe: &str,
+    tag: &str,
+    asset: Option<&str>,
+    install_dir: &Path,
+    manager: &BinManager,
+) -> Result<(), BinaError> {
+    let forge_type = match binary.forge {
+        Forge::GitHub => ForgeType::GitHub,
+        Forge::GitLab => ForgeType::GitLab,
+        Forge::Forgejo => ForgeType::Forgejo,
+    };
+    // ubi downloads and extracts in one go, so only the executable it produces can be cached
+    let exe_name = exe_file_name(exe, manager.target_os());
+    let triple = target_triple(manager.target_os(), manager.target_arch(), binary.libc);
+    let cache_path = cached_asset(binary, tag, &format!("{}/{}", triple, exe_name));
+    if cache_path.is_file() {
+        debug!("Installing {} {} from {}", exe, tag, cache_path.display());
+        fs::copy(&cache_path, install_dir.join(&exe_name))?;
+        return Ok(());
+    }
+
+    // ubi takes the only asset matching its regex, so the asset bina picked stays the one installed
+    let asset_regex = asset.map(|asset| format!("^{}$", regex::escape(asset)));
+    let mut builder = UbiBuilder::new()
+        .project(&binary.repo)
+        .forge(forge_type)
+        .api_base_url(api_base_url(binary))
+        .tag(tag)
+        .install_dir(install_dir)
+        .exe(exe);
+    if let Some(token) = forge_token(binary.forge, manager) {
+        builder = builder.token(token);
+    }
+    if let Some(matching) = &binary.matching {
+        builder = builder.matching(matching);
+    }
+    if let Some(matching_regex) = asset_regex.as_ref().or(binary.matching_regex.as_ref()) {
+        builder = builder.matching_regex(matching_regex);
+    }
+    if let Some(libc) = binary.libc {
+        builder = builder.is_musl(libc == Libc::Musl);
+    }
+    if manager.is_cross_target() {
+        let platform = Platform::find(&triple).ok_or_else(|| format!("Unsupported target platform {}", triple))?;
+        builder = builder.platform(platform);
+    }
+    debug!(
+        "Installing {} {} from {} ({:?}) with ubi",
+        exe, tag, binary.repo, binary.forge
+    );
+    let mut ubi = builder
+        .build()
+        .map_err(|e| BinaError::DownloadFailed(format!("Failed to set up ubi for {}: {}", binary.repo, e)))?;
+    let timeout = manager.http_timeout * UBI_TIMEOUT_FACTOR;
+    tokio::time::timeout(timeout, ubi.install_binary())
+        .await
+        .map_err(|_| {
+            BinaError::DownloadFailed(format!(
+                "Timed out downloading {} {} after {}s",
+                binary.repo,
+                tag,
+                timeout.as_secs()
+            ))
+        })?
+        .map_err(|e| BinaError::DownloadFailed(format!("Failed to download {} {}: {}", binary.repo, tag, e)))?;
+    cache_asset(&cache_path, &fs::read(install_dir.join(&exe_name))?);
+
+    Ok(())
+}
+
+/// Repository bina itself is released from
+const SELF_REPO: &str = "gunungpw/bina";
+
+/// Replaces the running executable with the latest release of bina
+pub async fn self_update(manager: &BinManager) -> Result<String, BinaError> {
+    if manager.is_cross_target() {
+        return Err("self-update replaces the running executable, so it can't use --target-os or --target-arch".into());
+    }
+    let binary = Binary {
+        name: "bina".to_string(),
+        repo: SELF_REPO.to_string(),
+        exe: vec!["bina".to_string()],
+        version_arg: default_version_arg(),
+        ..Default::default()
+    };
+    let release = fetch_release(&manager.client, &binary, manager, None).await?;
+    let current = env!("CARGO_PKG_VERSION");
+    let latest = parse_version(&release.tag, &binary, manager).unwrap_or(release.tag.clone());
+    if !Version::parse(current).is_outdated_by(&Version::parse(&latest)) {
+        return Ok(format!("bina {} is up to date", current));
+    }
+
+    // Follow symlinks so a bina installed by bina replaces its stored version
+    let exe = env::current_exe()?.canonicalize()?;
+    if manager.dry_run {
+        return Ok(format!(
+            "Would update bina {} -> {} at {}",
+            current,
+            latest,
+            exe.display()
+        ));
+    }
+    // Stage next to the executable so the final rename stays on one filesystem
+    let staging_dir = exe
+        .parent()
+        .ok_or("The running executable has no parent directory")?
+        .join(".bina-self-update");
+    fs::create_dir_all(&staging_dir)?;
+    let result: Result<(), BinaError> = async {
+        install_asset(
+            &binary,
+            &release.tag,
+            &AssetPin::default(),
+            &staging_dir,
+            manager,
+            &ProgressBar::hidden(),
+        )
+        .await?;
+        let new_exe = staging_dir.join(exe_file_name(binary.main_exe(), manager.target_os()));
+        smoke_test(&new_exe, &binary.version_arg)?;
+        replace_running_exe(&new_exe, &exe)
+    }
+    .await;
+    let _ = fs::remove_dir_all(&staging_dir);
+    result?;
+    Ok(format!("Updated bina {} -> {}", current, latest))
+}
+
+fn replace_running_exe(new_exe: &Path, exe: &Path) -> Result<(), BinaError> {
+    // Windows can't overwrite a running executable, but it can rename it out of the way
+    if cfg!(windows) {
+        let old_exe = exe.with_extension("old");
+        let _ = fs::remove_file(&old_exe);
+        fs::rename(exe, &old_exe)?;
+    }
+    fs::rename(new_exe, exe)?;
+    debug!("Replaced {}", exe.display());
+    Ok(())
+}
+
+fn has_cargo_binstall() -> bool {
+    Command::new("cargo")
+        .args(["binstall", "-V"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Installs a crate with cargo-binstall when available, otherwise builds it with cargo install
+fn install_from_crates_io(binary: &Binary, tag: &str, install_dir: &Path) -> Result<(), BinaError> {
+    let version = tag.strip_prefix('v').unwrap_or(tag);
+
+    if has_cargo_binstall() {
+        let status = Command::new("cargo")
+            .args(["binstall", "--no-confirm", "--force", "--version", version])
+            .arg("--install-path")
+            .arg(install_dir)
+            .arg(&binary.repo)
+            .status()?;
+        if !status.success() {
+            return Err(format!("cargo binstall failed for {}", binary.repo).into());
+        }
+        return Ok(());
+    }
+
+    // cargo install always writes to <root>/bin, so build into a scratch root and copy the exe out; the root is
+    // unique to this run and deleted when dropped, also on failure
+    let scratch = tempfile::Builder::new()
+        .prefix(&format!("bina-{}-", binary.name))
+        .tempdir()?;
+    let root = scratch.path();
+    let status = Command::new("cargo")
+        .args(["install", "--force", "--version", version])
+        .arg("--root")
+        .arg(root)
+        .arg(&binary.repo)
+        .status()?;
+    if !status.success() {
+        return Err(format!("cargo install failed for {}", binary.repo).into());
+    }
+    for exe in &binary.exe {
+        let exe_name = exe_file_name(exe, env::consts::OS);
+        fs::copy(root.join("bin").join(&exe_name), install_dir.join(&exe_name))?;
+    }
+    Ok(())
+}
+
+/// Expands `{tag}`, `{version}`, `{os}`, `{arch}` and `{libc}` in a download URL template
+fn expand_url(url: &str, tag: &str, binary: &Binary, manager: &BinManager) -> String {
+    url.replace("{tag}", tag)
+        .replace("{version}", tag.strip_prefix('v').unwrap_or(tag))
+        .replace("{os}", manager.target_os())
+        .replace("{arch}", manager.target_arch())
+        .replace("{libc}", binary.libc.unwrap_or(Libc::Gnu).as_str())
+}
+
+/// Last path segment of a URL, e.g. the asset name of a download link
+fn url_file_name(url: &str) -> &str {
+    url.split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .rsplit('/')
+        .next()
+        .unwrap_or("")
+}
+
+/// Whether ubi may install a binary bina can't: a forge release that nothing needs to check, throttle, or proxy,
+/// since ubi downloads with its own client and keeps the asset to itself
+fn ubi_may_install(binary: &Binary, pin: &AssetPin, manager: &BinManager) -> bool {
+    binary.url.is_none()
+        && manager.offline_assets.is_none()
+        && manager.rate_limit.is_none()
+        && manager.proxy.is_none()
+        && pin.name.is_none()
+        && pin.sha256.is_none()
+        && !binary.require_attestation
+        && binary.cosign_identity.is_none()
+}
+
+/// Installs from a release asset, a direct download, or a file in `--assets-dir`, fetching it once so the bytes
+/// that are verified are the ones the executables are extracted from, and returns the asset's name and digest
+///
+/// Assets bina can't pick or unpack are left to ubi when `ubi_may_install` allows, which leaves no digest.
+async fn install_asset(
+    binary: &Binary,
+    tag: &str,
+    pin: &AssetPin,
+    install_dir: &Path,
+    manager: &BinManager,
+    bar: &ProgressBar,
+) -> Result<AssetPin, BinaError> {
+    let (file_name, url, bytes) = match release_archive(binary, tag, pin.name.as_deref(), manager, bar).await {
+        Err(BinaError::AssetNotFound(e)) if ubi_may_install(binary, pin, manager) => {
+            debug!(
+                "No asset of {} stood out ({}), leaving the choice to ubi",
+                binary.name, e
+            );
+            install_with_ubi(binary, tag, None, install_dir, manager).await?;
+            return Ok(AssetPin::default());
+        }
+        result => result?,
+    };
+    let sha256 = hex::encode(Sha256::digest(&bytes));
+    let verified: Result<(), BinaError> = async {
+        if let Some(expected) = &pin.sha256 {
+            if !sha256.eq_ignore_ascii_case(expected) {
+                return Err(BinaError::VerificationFailed(format!(
+                    "Checksum mismatch for {}: expected {}, got {}",
+                    binary.name, expected, sha256
+                )));
+            }
+            debug!("Verified sha256 of {}", file_name);
+        }
+        match &binary.signature_pattern {
+            Some(_) if manager.offline_assets.is_some() => {
+                manager
+                    .progress
+                    .suspend(|| warn!("Not verifying the signature of {} offline", file_name));
+            }
+            Some(signature_pattern) => {
+                bar.set_message("verifying signature");
+                let pubkey = binary
+                    .pubkey
+                    .as_deref()
+                    .ok_or_else(|| format!("Binary '{}' sets signature_pattern but no pubkey", binary.name))?;
+                let signature_url = expand_url(&signature_pattern.replace("{url}", &url), tag, binary, manager);
+                debug!("Downloading signature for {} from {}", binary.name, signature_url);
+                let signature = fetch_bytes(&manager.client, &signature_url, binary, manager).await?;
+                verify_signature(&bytes, &file_name, &signature, &signature_url, pubkey, install_dir).map_err(|e| {
+                    BinaError::VerificationFailed(format!("Signature verification failed for {}: {}", binary.name, e))
+                })?;
+                debug!("Verified signature of {}", file_name);
+            }
+            None => {}
+        }
+        if !binary.require_attestation && binary.cosign_identity.is_none() {
+            return Ok(());
+        }
+        // gh and cosign read the asset from disk
+        let asset_dir = install_dir.join("asset");
+        fs::create_dir_all(&asset_dir)?;
+        let asset_path = asset_dir.join(&file_name);
+        fs::write(&asset_path, &bytes)?;
+        if binary.require_attestation {
+            bar.set_message("verifying attestation");
+            manager
+                .progress
+                .suspend(|| verify_attestation(&asset_path, binary, manager))?;
+            debug!("Verified the attestation of {}", file_name);
+        }
+        if let Some(identity) = &binary.cosign_identity {
+            bar.set_message("verifying cosign signature");
+            let pattern = binary.cosign_bundle.as_deref().unwrap_or(DEFAULT_COSIGN_BUNDLE);
+            let bundle_url = expand_url(&pattern.replace("{url}", &url), tag, binary, manager);
+            debug!("Downloading the Sigstore bundle of {} from {}", binary.name, bundle_url);
+            let bundle_path = asset_dir.join(url_file_name(&bundle_url));
+            fs::write(
+                &bundle_path,
+                fetch_bytes(&manager.client, &bundle_url, binary, manager).await?,
+            )?;
+            manager
+                .progress
+                .suspend(|| verify_cosign(&asset_path, &bundle_path, identity, binary))?;
+            debug!("Verified the cosign signature of {}", file_name);
+        }
+        Ok(())
+    }
+    .await;
+    if let Err(e) = verified {
+        // Don't let a tampered asset be reused
+        if manager.offline_assets.is_none() {
+            let _ = fs::remove_file(cached_asset(binary, tag, &file_name));
+        }
+        return Err(e);
+    }
+
+    match write_asset_exe(&bytes, &file_name, binary, install_dir, manager, bar) {
+        Err(e) if ubi_may_install(binary, pin, manager) => {
+            debug!("Could not unpack {} ({}), leaving it to ubi", file_name, e);
+            install_with_ubi(binary, tag, Some(&file_name), install_dir, manager).await?;
+        }
+        result => result?,
+    }
+    Ok(AssetPin {
+        name: Some(file_name),
+        sha256: Some(sha256),
+    })
+}
+
+/// Fetches a small file such as a signature, reading file:// URLs from disk
+async fn fetch_bytes(client: &Client, url: &str, binary: &Binary, manager: &BinManager) -> Result<Vec<u8>, BinaError> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return Ok(fs::read(path)?);
+    }
+    let request = client.get(url);
+    Ok(
+        send_with_retries(authorize_download(request, url, binary, manager), manager)
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec(),
+    )
+}
+
+/// The asset URL on each of the binary's mirrors, e.g. https://mirror.example.com/gh/owner/repo/releases/...
+/// for the mirror https://mirror.example.com/gh
+fn mirror_urls(url: &str, binary: &Binary) -> Vec<String> {
+    // Asset API URLs need the token, which mirrors must not see
+    if binary.mirrors.is_empty() || url.starts_with(api_base_url(binary)) {
+        return Vec::new();
+    }
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return Vec::new();
+    };
+    let path = match parsed.query() {
+        Some(query) => format!("{}?{}", parsed.path(), query),
+        None => parsed.path().to_string(),
+    };
+    binary
+        .mirrors
+        .iter()
+        .map(|mirror| format!("{}{}", mirror.trim_end_matches('/'), path))
+        .collect()
+}
+
+/// Downloads an asset into `partial`, resuming an earlier attempt, and returns its contents
+///
+/// When the download fails for good, the binary's mirrors are tried in order.
+async fn download_asset(
+    client: &Client,
+    url: &str,
+    partial: &Path,
+    binary: &Binary,
+    manager: &BinManager,
+    bar: &ProgressBar,
+) -> Result<Vec<u8>, BinaError> {
+    if let Some(parent) = partial.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut result = download_with_retries(client, url, partial, binary, manager, bar).await;
+    for mirror_url in mirror_urls(url, binary) {
+        let Err(e) = &result else {
+            break;
+        };
+        bar.suspend(|| warn!("{}, trying {}", e, mirror_url));
+        // A partial download from another server may not be a prefix of the mirror's copy
+        let _ = fs::remove_file(partial);
+        result = download_with_retries(client, &mirror_url, partial, binary, manager, bar).await;
+    }
+    result
+}
+
+/// Downloads `url` into `partial`, retrying interrupted transfers, each try resuming where the last stopped
+async fn download_with_retries(
+    client: &Client,
+    url: &str,
+    partial: &Path,
+    binary: &Binary,
+    manager: &BinManager,
+    bar: &ProgressBar,
+) -> Result<Vec<u8>, BinaError> {
+    let mut attempt = 1;
+    loop {
+        match download_range(client, url, partial, binary, manager, bar).await {
+            Ok(()) => break,
+            // Connection drops and server errors are worth another try, anything else won't change
+            Err(BinaError::Network(e))
+                if attempt < manager.retry.attempts && e.status().is_none_or(|s| s.is_server_error()) =>
+            {
+                bar.suspend(|| warn!("Download of {} interrupted ({}), retrying", url, e));
+                tokio::time::sleep(manager.retry.delay(attempt)).await;
+                attempt += 1;
+            }
+            Err(BinaError::Network(e)) => {
+                return Err(BinaError::DownloadFailed(format!("Failed to download {}: {}", url, e)));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    bar.set_style(spinner_style());
+    Ok(fs::read(partial)?)
+}
+
+/// Appends the rest of `url` to `partial` with a range request, starting over if the server ignores the range
+async fn download_range(
+    client: &Client,
+    url: &str,
+    partial: &Path,
+    binary: &Binary,
+    manager: &BinManager,
+    bar: &ProgressBar,
+) -> Result<(), BinaError> {
+    let offset = fs::metadata(partial).map(|metadata| metadata.len()).unwrap_or(0);
+    let request = client.get(url);
+    let mut request = authorize_download(request, url, binary, manager);
+    if offset > 0 {
+        debug!("Resuming {} at byte {}", url, offset);
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+    let response = request.send().await?;
+    match response.status() {
+        reqwest::StatusCode::NOT_FOUND => return Err(BinaError::AssetNotFound(url.to_string())),
+        // The partial file is no prefix of the asset, perhaps because the asset changed
+        reqwest::StatusCode::RANGE_NOT_SATISFIABLE => {
+            fs::remove_file(partial)?;
+            return Box::pin(download_range(client, url, partial, binary, manager, bar)).await;
+        }
+        _ => {}
+    }
+    let mut response = response.error_for_status()?;
+
+    let resumed = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut file = if resumed {
+        fs::OpenOptions::new().append(true).open(partial)?
+    } else {
+        fs::File::create(partial)?
+    };
+    let start = if resumed { offset } else { 0 };
+    if let Some(length) = response.content_length() {
+        bar.set_style(download_style());
+        bar.set_length(start + length);
+    }
+    bar.set_position(start);
+    while let Some(chunk) = response.chunk().await? {
+        if let Some(rate_limit) = &manager.rate_limit {
+            rate_limit.take(chunk.len()).await;
+        }
+        file.write_all(&chunk)?;
+        bar.inc(chunk.len() as u64);
+    }
+    Ok(())
+}
+
+/// Extracts the executable from a downloaded asset into `install_dir`
+fn write_asset_exe(
+    bytes: &[u8],
+    file_name: &str,
+    binary: &Binary,
+    install_dir: &Path,
+    manager: &BinManager,
+    bar: &ProgressBar,
+) -> Result<(), BinaError> {
+    bar.set_message("extracting");
+    for exe in &binary.exe {
+        let exe_bytes = extract_exe(bytes, file_name, exe)?;
+        fs::write(install_dir.join(exe_file_name(exe, manager.target_os())), exe_bytes)?;
+    }
+    Ok(())
+}
+
+/// Files in the assets directory whose names mention the binary
+fn offline_candidates(assets_dir: &Path, binary: &Binary) -> Vec<String> {
+    let names = [binary.name.to_lowercase(), binary.main_exe().to_lowercase()];
+    fs::read_dir(assets_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|file_name| {
+            let file_name = file_name.to_lowercase();
+            names.iter().any(|name| file_name.contains(name.as_str()))
+        })
+        .collect()
+}
+
+/// Newest version among the binary's assets in the offline directory
+fn newest_offline_tag(binary: &Binary, manager: &BinManager) -> Result<String, BinaError> {
+    let assets_dir = manager.offline_assets.as_deref().unwrap_or(Path::new("."));
+    offline_candidates(assets_dir, binary)
+        .iter()
+        .filter_map(|file_name| parse_version(file_name, binary, manager))
+        .max_by(|a, b| {
+            Version::parse(a)
+                .partial_cmp(&Version::parse(b))
+                .unwrap_or(Ordering::Equal)
+        })
+        .ok_or_else(|| {
+            BinaError::AssetNotFound(format!(
+                "for {} with a version in its name in {}",
+                binary.name,
+                assets_dir.display()
+            ))
+        })
+}
+
+/// The pre-downloaded asset named after the binary and `tag`, or exactly `pinned`
+fn offline_asset(tag: &str, pinned: Option<&str>, binary: &Binary, manager: &BinManager) -> Result<PathBuf, BinaError> {
+    let assets_dir = manager.offline_assets.as_deref().unwrap_or(Path::new("."));
+    if let Some(pinned) = pinned {
+        let asset = assets_dir.join(pinned);
+        if !asset.is_file() {
+            return Err(BinaError::AssetNotFound(asset.display().to_string()));
+        }
+        return Ok(asset);
+    }
+    let version = tag.trim_start_matches('v');
+    let candidates: Vec<String> = offline_candidates(assets_dir, binary)
+        .into_iter()
+        .filter(|file_name| file_name.contains(version))
+        .collect();
+    let file_name = match likely_asset(&candidates, binary, manager) {
+        Some(file_name) => file_name,
+        // Direct-URL assets often carry no platform in their name
+        None if candidates.len() == 1 => candidates[0].clone(),
+        None => {
+            return Err(BinaError::AssetNotFound(format!(
+                "for {} {} in {}",
+                binary.name,
+                tag,
+                assets_dir.display()
+            )));
+        }
+    };
+    Ok(assets_dir.join(file_name))
+}
+
+/// Name, URL, and contents of the asset a binary installs from, `pinned` or the one fitting the platform best,
+/// downloading it unless it's cached; assets from `--assets-dir` get a file:// URL
+async fn release_archive(
+    binary: &Binary,
+    tag: &str,
+    pinned: Option<&str>,
+    manager: &BinManager,
+    bar: &ProgressBar,
+) -> Result<(String, String, Vec<u8>), BinaError> {
+    if manager.offline_assets.is_some() {
+        let asset = offline_asset(tag, pinned, binary, manager)?;
+        let file_name = asset.file_name().and_then(OsStr::to_str).unwrap_or("").to_string();
+        return Ok((file_name, format!("file://{}", asset.display()), fs::read(&asset)?));
+    }
+    let client = &manager.client;
+    let (file_name, url) = match (&binary.url, binary.source) {
+        (_, Source::CratesIo) => return Err("crates.io builds ship no release archive".into()),
+        (Some(url), Source::Release) => {
+            let url = expand_url(url, tag, binary, manager);
+            (url_file_name(&url).to_string(), url)
+        }
+        (None, Source::Release) => {
+            let release = fetch_release(client, binary, manager, Some(tag)).await?;
+            let asset = match pinned {
+                Some(pinned) if release.assets.iter().any(|asset| asset == pinned) => pinned.to_string(),
+                Some(pinned) => {
+                    return Err(BinaError::AssetNotFound(format!(
+                        "{} in {} {}",
+                        pinned, binary.repo, tag
+                    )));
+                }
+                None => likely_asset(&release.assets, binary, manager)
+                    .ok_or_else(|| BinaError::AssetNotFound(format!("for {} {}", binary.name, tag)))?,
+            };
+            // GitHub's download URLs ignore tokens, so private assets have to come through the API
+            let urls = match forge_token(binary.forge, manager) {
+                Some(_) if release.asset_api_urls.contains_key(&asset) => &release.asset_api_urls,
+                _ => &release.asset_urls,
+            };
+            let url = urls
+                .get(&asset)
+                .cloned()
+                .ok_or_else(|| BinaError::AssetNotFound(asset.clone()))?;
+            (asset, url)
+        }
+    };
+    let cache_path = cached_asset(binary, tag, &file_name);
+    if cache_path.is_file() {
+        debug!("Using cached {}", cache_path.display());
+        return Ok((file_name, url, fs::read(&cache_path)?));
+    }
+    let partial = partial_path(&cache_path);
+    let bytes = download_asset(client, &url, &partial, binary, manager, bar).await?;
+    fs::rename(&partial, &cache_path)?;
+    debug!("Cached {}", cache_path.display());
+    Ok((file_name, url, bytes))
+}
+
+/// Where a man page from a release archive goes, e.g. doc/rg.1 to $XDG_DATA_HOME/man/man1/rg.1
+fn man_page_target(path: &Path, data_home: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let section = name.strip_suffix(".gz").unwrap_or(name).rsplit_once('.')?.1;
+    let is_section = section.len() == 1 && section.chars().all(|c| c.is_ascii_digit());
+    is_section.then(|| data_home.join("man").join(format!("man{}", section)).join(name))
+}
+
+/// Where a completion script from a release archive's completions directory goes, by the shell its name suggests
+fn completion_target(path: &Path, data_home: &Path) -> Option<PathBuf> {
+    let in_completions_dir = path
+        .parent()?
+        .components()
+        .any(|component| component.as_os_str().to_string_lossy().contains("complet"));
+    if !in_completions_dir {
+        return None;
+    }
+    let name = path.file_name()?.to_str()?;
+    if name.ends_with(".fish") {
+        Some(data_home.join("fish/vendor_completions.d").join(name))
+    } else if let Some(command) = name
+        .strip_suffix(".zsh")
+        .or_else(|| name.strip_prefix('_').filter(|rest| !rest.contains('.')))
+    {
+        let command = command.trim_start_matches('_');
+        Some(data_home.join("zsh/site-functions").join(format!("_{}", command)))
+    } else {
+        let command = name
+            .strip_suffix(".bash")
+            .or_else(|| name.strip_suffix(".bash-completion"))?;
+        Some(data_home.join("bash-completion/completions").join(command))
+    }
+}
+
+/// Copies the man pages and completions a binary opts into out of its release archive
+async fn install_extras(
+    binary: &Binary,
+    tag: &str,
+    asset: Option<&str>,
+    manager: &BinManager,
+    bar: &ProgressBar,
+) -> Result<Vec<String>, BinaError> {
+    bar.set_message("installing man pages and completions");
+    let (file_name, _, bytes) = release_archive(binary, tag, asset, manager, bar).await?;
+    let data_home = PathBuf::from(base_dir("XDG_DATA_HOME", "LOCALAPPDATA", ".local/share"));
+    let target = |path: &Path| {
+        (binary.man_pages)
+            .then(|| man_page_target(path, &data_home))
+            .flatten()
+            .or_else(|| {
+                binary
+                    .completions
+                    .then(|| completion_target(path, &data_home))
+                    .flatten()
+            })
+    };
+    let files = extract_files(&bytes, &file_name, |path| target(path).is_some())?;
+    if files.is_empty() {
+        debug!("{} has no man pages or completions", file_name);
+    }
+    let mut installed = vec![];
+    for (path, contents) in files {
+        let Some(target) = target(&path) else {
+            continue;
+        };
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, contents)?;
+        debug!("Installed {} to {}", path.display(), target.display());
+        installed.push(target.display().to_string());
+    }
+    Ok(installed)
+}
+
+/// Deletes man pages and completions recorded in the state file, skipping ones already gone
+fn remove_extras(files: &[String]) {
+    for file in files {
+        match fs::remove_file(file) {
+            Ok(()) => debug!("Removed {}", file),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Could not remove {}: {}", file, e),
+        }
+    }
+}
+
+/// Files in a tar or zip asset whose paths satisfy `wanted`; other assets contain none
+fn extract_files(
+    bytes: &[u8],
+    file_name: &str,
+    wanted: impl Fn(&Path) -> bool,
+) -> Result<Vec<(PathBuf, Vec<u8>)>, BinaError> {
+    fn from_tar<R: Read>(reader: R, wanted: impl Fn(&Path) -> bool) -> Result<Vec<(PathBuf, Vec<u8>)>, BinaError> {
+        let mut files = vec![];
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            if entry.header().entry_type().is_file() && wanted(&path) {
+                let mut contents = vec![];
+                entry.read_to_end(&mut contents)?;
+                files.push((path, contents));
+            }
+        }
+        Ok(files)
+    }
+
+    if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+        from_tar(GzDecoder::new(bytes), wanted)
+    } else if file_name.ends_with(".tar.xz") || file_name.ends_with(".txz") {
+        from_tar(XzDecoder::new(bytes), wanted)
+    } else if file_name.ends_with(".tar") {
+        from_tar(bytes, wanted)
+    } else if file_name.ends_with(".zip") {
+        let mut files = vec![];
+        let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let path = PathBuf::from(file.name());
+            if file.is_file() && wanted(&path) {
+                let mut contents = vec![];
+                file.read_to_end(&mut contents)?;
+                files.push((path, contents));
+            }
+        }
+        Ok(files)
+    } else {
+        Ok(vec![])
+    }
+}
+
+/// Checks a detached signature, using minisign for `.minisig` files and gpg otherwise
+fn verify_signature(
+    asset: &[u8],
+    asset_name: &str,
+    signature: &[u8],
+    signature_url: &str,
+    pubkey: &str,
+    work_dir: &Path,
+) -> Result<(), BinaError> {
+    if signature_url.ends_with(".minisig") {
+        let public_key = minisign_verify::PublicKey::from_base64(pubkey)?;
+        let signature = minisign_verify::Signature::decode(&String::from_utf8_lossy(signature))?;
+        public_key.verify(asset, &signature, false)?;
+        return Ok(());
+    }
+
+    // gpg needs files on disk; a throwaway keyring keeps the user's keyring untouched
+    let asset_path = work_dir.join(asset_name);
+    let signature_path = work_dir.join(format!("{}.sig", asset_name));
+    let keyring = work_dir.join("bina-keyring.gpg");
+    fs::write(&asset_path, asset)?;
+    fs::write(&signature_path, signature)?;
+    let gpg = |args: &[&OsStr]| -> Result<bool, BinaError> {
+        let output = Command::new("gpg")
+            .args(["--batch", "--no-default-keyring", "--keyring"])
+            .arg(&keyring)
+            .args(args)
+            .output()?;
+        debug!("gpg: {}", String::from_utf8_lossy(&output.stderr).trim());
+        Ok(output.status.success())
+    };
+    if !gpg(&[OsStr::new("--import"), OsStr::new(pubkey)])? {
+        return Err(format!("gpg could not import key {}", pubkey).into());
+    }
+    if !gpg(&[
+        OsStr::new("--verify"),
+        signature_path.as_os_str(),
+        asset_path.as_os_str(),
+    ])? {
+        return Err("gpg rejected the signature".into());
+    }
+    Ok(())
+}
+
+fn is_exe_entry(path: &Path, exe: &str) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name == exe || name.strip_suffix(".exe") == Some(exe))
+}
+
+fn extract_from_tar<R: Read>(reader: R, exe: &str) -> Result<Vec<u8>, BinaError> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_file() && is_exe_entry(&entry.path()?, exe) {
+            let mut bytes = vec![];
+            entry.read_to_end(&mut bytes)?;
+            return Ok(bytes);
+        }
+    }
+    Err(format!("Executable '{}' not found in archive", exe).into())
+}
+
+/// Pulls the executable out of a downloaded asset, which may be a bare binary
+fn extract_exe(bytes: &[u8], file_name: &str, exe: &str) -> Result<Vec<u8>, BinaError> {
+    if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+        extract_from_tar(GzDecoder::new(bytes), exe)
+    } else if file_name.ends_with(".tar.xz") || file_name.ends_with(".txz") {
+        extract_from_tar(XzDecoder::new(bytes), exe)
+    } else if file_name.ends_with(".tar") {
+        extract_from_tar(bytes, exe)
+    } else if file_name.ends_with(".zip") {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.is_file() && is_exe_entry(Path::new(file.name()), exe) {
+                let mut bytes = vec![];
+                file.read_to_end(&mut bytes)?;
+                return Ok(bytes);
+            }
+        }
+        Err(format!("Executable '{}' not found in archive", exe).into())
+    } else if file_name.ends_with(".gz") {
+        let mut bytes_out = vec![];
+        GzDecoder::new(bytes).read_to_end(&mut bytes_out)?;
+        if !has_exe_magic(&bytes_out) {
+            return Err(format!("{} does not contain an executable", file_name).into());
+        }
+        Ok(bytes_out)
+    } else if has_exe_magic(bytes) {
+        Ok(bytes.to_vec())
+    } else {
+        // Likely an HTML error page or an installer such as a .dmg, which must not end up in the bin directory
+        Err(format!("{} is neither a supported archive nor an executable", file_name).into())
+    }
+}
+
+/// Whether `bytes` start like something that runs: an ELF, Mach-O, or PE binary, or a script with a shebang
+fn has_exe_magic(bytes: &[u8]) -> bool {
+    const MAGIC: &[&[u8]] = &[
+        b"\x7fELF",
+        b"\xfe\xed\xfa\xce",
+        b"\xfe\xed\xfa\xcf",
+        b"\xce\xfa\xed\xfe",
+        b"\xcf\xfa\xed\xfe",
+        // Universal Mach-O binaries
+        b"\xca\xfe\xba\xbe",
+        b"MZ",
+        b"#!",
+    ];
+    MAGIC.iter().any(|magic| bytes.starts_with(magic))
+}
+
+/// Directory a binary is installed into
+fn binary_dir(binary: &Binary, manager: &BinManager) -> String {
+    match binary.install_dir.as_deref() {
+        Some(dir) => match (dir.strip_prefix("~/"), env::var("HOME")) {
+            (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+            _ => dir.to_string(),
+        },
+        None => manager.xdg_bin_home.clone(),
+    }
+}
+
+/// Where the executable of `binary` is installed
+pub fn exe_path(binary: &Binary, manager: &BinManager) -> PathBuf {
+    Path::new(&binary_dir(binary, manager)).join(exe_file_name(binary.main_exe(), manager.target_os()))
+}
+
+fn installed_binaries(xdg_bin_home: &str) -> Vec<String> {
+    // A missing directory (e.g. during a dry run) simply has nothing installed
+    fs::read_dir(xdg_bin_home)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry: Result<DirEntry, _>| entry.ok().and_then(|e| e.file_name().into_string().ok()))
+        .collect()
+}
+
+/// A version in whichever scheme a tool follows, so installed and latest versions compare sensibly
+#[derive(Debug, Clone)]
+pub enum Version {
+    /// Dot-separated numbers of any length, e.g. 1.2 or 1.2.3.4, with an optional pre-release such as `rc.1`
+    Semver { parts: Vec<u64>, pre: Option<String> },
+    /// A release date, e.g. 2024.05.01, 20240501, or the 2024-05-01 in nightly-2024-05-01
+    Calver(Vec<u64>),
+    /// Anything else, such as a commit hash, which only compares for equality
+    Opaque(String),
+}
+
+/// A date inside a tag, such as the one in nightly-2024-05-01
+static DATE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d{4})-(\d{2})-(\d{2})").expect("the date pattern is valid"));
+
+impl Version {
+    pub fn parse(text: &str) -> Version {
+        let text = text.trim();
+        if let Some(date) = DATE_REGEX.captures(text) {
+            return Version::Calver((1..=3).filter_map(|group| date[group].parse().ok()).collect());
+        }
+
+        let version = text.strip_prefix(['v', 'V']).unwrap_or(text);
+        // Build metadata after `+` never affects ordering, also when it follows a pre-release
+        let version = version.split_once('+').map_or(version, |(version, _)| version);
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (version, None),
+        };
+        let parts: Option<Vec<u64>> = core.split('.').map(|part| part.parse().ok()).collect();
+        match parts {
+            Some(parts) if parts.len() == 1 && (19700101..=29991231).contains(&parts[0]) => {
+                Version::Calver(vec![parts[0] / 10000, parts[0] / 100 % 100, parts[0] % 100])
+            }
+            Some(parts) if parts.len() > 1 && (1970..=2999).contains(&parts[0]) => Version::Calver(parts),
+            Some(parts) => Version::Semver {
+                parts,
+                pre: pre.filter(|pre| !pre.is_empty()).map(String::from),
+            },
+            None => Version::Opaque(version.to_string()),
+        }
+    }
+
+    /// Whether `latest` should replace this version: it's newer, or differs where versions can't be ordered
+    pub fn is_outdated_by(&self, latest: &Version) -> bool {
+        self.partial_cmp(latest)
+            .is_none_or(|ordering| ordering == Ordering::Less)
+    }
+}
+
+/// Compares numeric parts, treating missing parts as zero so 1.2 equals 1.2.0
+fn compare_parts(a: &[u64], b: &[u64]) -> Ordering {
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Compares semver pre-releases: a release sorts after its pre-releases, numeric identifiers compare as numbers
+/// and before alphanumeric ones, which compare in ASCII order, and a longer list wins when the shared ones tie
+fn compare_pre(a: Option<&str>, b: Option<&str>) -> Ordering {
+    let (a, b) = match (a, b) {
+        (None, None) => return Ordering::Equal,
+        (None, Some(_)) => return Ordering::Greater,
+        (Some(_), None) => return Ordering::Less,
+        (Some(a), Some(b)) => (a, b),
+    };
+    let (mut a, mut b) = (a.split('.'), b.split('.'));
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+        match (self, other) {
+            (Version::Semver { parts: a, pre: a_pre }, Version::Semver { parts: b, pre: b_pre }) => {
+                Some(compare_parts(a, b).then_with(|| compare_pre(a_pre.as_deref(), b_pre.as_deref())))
+            }
+            (Version::Calver(a), Version::Calver(b)) => Some(compare_parts(a, b)),
+            (Version::Opaque(a), Version::Opaque(b)) => (a == b).then_some(Ordering::Equal),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Version) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+/// Finds a version in `text` with the binary's `version_regex`, or the default `x.y[.z…]` pattern
+///
+/// The first capture group is the version, or the whole match when the regex has no groups.
+pub fn parse_version(text: &str, binary: &Binary, manager: &BinManager) -> Option<String> {
+    let version_regex = binary.version_regex.as_deref().and_then(|regex| Regex::new(regex).ok());
+    version_regex
+        .as_ref()
+        .unwrap_or(&manager.regex)
+        .captures(text)
+        .and_then(|cap| cap.get(1).or(cap.get(0)).map(|m| m.as_str().to_string()))
+}
+
+fn installed_version(exe_path: &Path, binary: &Binary, manager: &BinManager) -> Option<String> {
+    let version_arg = &binary.version_arg;
+    let version_output = match cached_probe(exe_path, version_arg, manager) {
+        Some(output) => output,
+        None => {
+            let output = match Command::new(exe_path).args(version_arg.split_whitespace()).output() {
+                Ok(output) => output,
+                Err(e) => {
+                    debug!("Failed to run {} {}: {}", exe_path.display(), version_arg, e);
+                    return None;
+                }
+            };
+            // Some tools print their version to stderr, so search both streams, stdout first
+            let version_output = format!(
+                "{}\n{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            record_probe(exe_path, version_arg, &version_output, manager);
+            version_output
+        }
+    };
+    let version = parse_version(&version_output, binary, manager);
+    debug!(
+        "{} {} printed {:?}, parsed version {:?}",
+        exe_path.display(),
+        version_arg,
+        version_output.trim(),
+        version
+    );
+    version
+}
+
+/// Size and modification time of an executable, which decide whether a cached probe still applies
+fn probe_stamp(exe_path: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(exe_path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((metadata.len(), u64::try_from(modified.as_nanos()).ok()?))
+}
+
+/// Earlier output of `version_arg` for an executable that hasn't changed since
+fn cached_probe(exe_path: &Path, version_arg: &str, manager: &BinManager) -> Option<String> {
+    let (size, modified) = probe_stamp(exe_path)?;
+    let output = with_probes(manager, |probes| {
+        let probe = probes.get(exe_path.to_string_lossy().as_ref())?;
+        (probe.size == size && probe.modified == modified && probe.version_arg == version_arg)
+            .then(|| probe.output.clone())
+    })?;
+    debug!("Using the cached version output of {}", exe_path.display());
+    Some(output)
+}
+
+fn record_probe(exe_path: &Path, version_arg: &str, output: &str, manager: &BinManager) {
+    let Some((size, modified)) = probe_stamp(exe_path) else {
+        return;
+    };
+    let path = exe_path.to_string_lossy().into_owned();
+    let probe = VersionProbe {
+        path: path.clone(),
+        size,
+        modified,
+        version_arg: version_arg.to_string(),
+        output: output.to_string(),
+    };
+    with_probes(manager, |probes| probes.insert(path, probe));
+}
+
+/// Runs `f` on the manager's version probes, reading them from the state file the first time
+fn with_probes<T>(manager: &BinManager, f: impl FnOnce(&mut HashMap<String, VersionProbe>) -> T) -> T {
+    let mut probes = manager.probes.lock().unwrap_or_else(|e| e.into_inner());
+    f(probes.get_or_insert_with(|| {
+        read_state()
+            .map(|state| {
+                state
+                    .probes
+                    .into_iter()
+                    .map(|probe| (probe.path.clone(), probe))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }))
+}
+
+/// Writes the version probes of this run to the state file, dropping those of executables that are gone
+fn save_probes(manager: &BinManager) {
+    let probes = manager.probes.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(probes) = probes.as_ref() else {
+        return;
+    };
+    if manager.dry_run {
+        return;
+    }
+    let result = read_state().and_then(|mut state| {
+        let mut kept: Vec<VersionProbe> = probes
+            .values()
+            .filter(|probe| Path::new(&probe.path).exists())
+            .cloned()
+            .collect();
+        kept.sort_by(|a, b| a.path.cmp(&b.path));
+        if kept == state.probes {
+            return Ok(());
+        }
+        state.probes = kept;
+        write_state(&state)
+    });
+    if let Err(e) = result {
+        debug!("Failed to save version probes: {}", e);
+    }
+}
+
+/// Reads the version from the linked version directory, falling back to running the executable
+pub fn current_version(binary: &Binary, manager: &BinManager) -> Option<String> {
+    let exe_path = exe_path(binary, manager);
+    // A tag without a version, like a commit hash, is still what was installed; `latest` says nothing though
+    linked_tag(&exe_path)
+        .filter(|tag| tag != "latest")
+        .map(|tag| parse_version(&tag, binary, manager).unwrap_or(tag))
+        .or_else(|| installed_version(&exe_path, binary, manager))
+}
+
+/// Version number of the latest release, None when it can't be fetched
+pub async fn latest_version(client: &Client, binary: &Binary, manager: &BinManager) -> Option<String> {
+    // Direct URLs have no release API to ask
+    if binary.url.is_some() {
+        return None;
+    }
+    match check_latest_release(client, binary, manager).await {
+        Ok(latest) => Some(parse_version(&latest, binary, manager).unwrap_or(latest)),
+        Err(e) => {
+            debug!("Failed to fetch latest release of {}: {}", binary.repo, e);
+            None
+        }
+    }
+}
+
+/// Collects the configuration, installed version, and latest release of one binary
+pub async fn binary_info(bin_name: &str, manager: &BinManager) -> Result<BinaryInfo, BinaError> {
+    let binary = manager
+        .data
+        .get(bin_name)
+        .ok_or_else(|| BinaError::BinaryNotFound(bin_name.to_string()))?;
+    let exe_path = exe_path(binary, manager);
+    let installed_version = if exe_path.exists() {
+        current_version(binary, manager)
+    } else {
+        None
+    };
+
+    let mut info = BinaryInfo {
+        name: binary.name.clone(),
+        repo: binary.repo.clone(),
+        exe: binary.exe.join(", "),
+        install_path: exe_path.display().to_string(),
+        installed_version,
+        latest_tag: binary.version.clone(),
+        release_date: None,
+        asset: None,
+        release_url: None,
+    };
+    let client = &manager.client;
+    match (&binary.url, binary.source) {
+        (_, Source::CratesIo) => {
+            match check_latest_crate(client, &binary.repo, manager).await {
+                Ok(latest) => info.latest_tag = info.latest_tag.or(Some(latest)),
+                Err(e) => warn!("Could not fetch the latest version of {}: {}", binary.repo, e),
+            }
+            info.release_url = Some(format!("https://crates.io/crates/{}", binary.repo));
+        }
+        (Some(url), Source::Release) => {
+            info.asset = binary
+                .version
+                .as_deref()
+                .map(|tag| expand_url(url, tag, binary, manager))
+                .or(Some(url.clone()));
+        }
+        (None, Source::Release) => match fetch_release(client, binary, manager, binary.version.as_deref()).await {
+            Ok(release) => {
+                info.asset = likely_asset(&release.assets, binary, manager);
+                info.latest_tag = Some(release.tag);
+                info.release_date = release.published;
+                info.release_url = release.url;
+            }
+            Err(e) => warn!("Could not fetch release details for {}: {}", binary.repo, e),
+        },
+    }
+    Ok(info)
+}
+
+/// Resolves a binary's executable, whether bina installed it, and whether PATH finds another copy first
+pub fn which_binary(bin_name: &str, manager: &BinManager) -> Result<WhichInfo, BinaError> {
+    let binary = manager
+        .data
+        .get(bin_name)
+        .ok_or_else(|| BinaError::BinaryNotFound(bin_name.to_string()))?;
+    let exe_path = exe_path(binary, manager);
+    let installed = exe_path.exists();
+    let state = read_state()?;
+    let tag = linked_tag(&exe_path).or_else(|| {
+        state
+            .installed
+            .iter()
+            .find(|installed| installed.name == bin_name && Path::new(&installed.path) == exe_path)
+            .map(|installed| installed.tag.clone())
+    });
+    Ok(WhichInfo {
+        name: binary.name.clone(),
+        path: exe_path.display().to_string(),
+        installed,
+        managed: installed && state.manages(bin_name, &exe_path),
+        version: installed.then(|| current_version(binary, manager)).flatten(),
+        tag,
+        shadowed_by: shadowing_exe(&exe_path).map(|path| path.display().to_string()),
+    })
+}
+
+/// First executable on PATH with the same file name as `exe_path`, unless it's in `exe_path`'s directory
+fn shadowing_exe(exe_path: &Path) -> Option<PathBuf> {
+    let file_name = exe_path.file_name()?;
+    let exe_dir = exe_path.parent().and_then(|dir| dir.canonicalize().ok());
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(file_name))
+        .find(|candidate| candidate.is_file())
+        .filter(|candidate| candidate.parent().and_then(|dir| dir.canonicalize().ok()) != exe_dir)
+}
+
+/// Web address of a binary's repository, derived from its forge's API root
+fn repo_web_url(binary: &Binary) -> String {
+    if binary.source == Source::CratesIo {
+        return format!("https://crates.io/crates/{}", binary.repo);
+    }
+    let api_base_url = api_base_url(binary);
+    // GitLab, Forgejo, and GitHub Enterprise serve their API under /api/, github.com from api.github.com
+    let root = api_base_url
+        .split_once("/api/")
+        .map_or(api_base_url, |(root, _)| root)
+        .replace("://api.", "://");
+    format!("{}/{}", root, binary.repo)
+}
+
+/// Where the asset of an installed release came from: the asset bina.lock records, or else the one that fits the
+/// platform best
+async fn download_url(
+    client: &Client,
+    binary: &Binary,
+    tag: &str,
+    locked_asset: Option<&str>,
+    manager: &BinManager,
+) -> Option<String> {
+    match (&binary.url, binary.source) {
+        (_, Source::CratesIo) => Some(format!(
+            "https://crates.io/api/v1/crates/{}/{}/download",
+            binary.repo,
+            tag.strip_prefix('v').unwrap_or(tag)
+        )),
+        (Some(url), Source::Release) => Some(expand_url(url, tag, binary, manager)),
+        (None, Source::Release) => {
+            let release = fetch_release(client, binary, manager, Some(tag))
+                .await
+                .inspect_err(|e| debug!("Cannot look up the asset of {} {}: {}", binary.name, tag, e))
+                .ok()?;
+            let asset = match locked_asset {
+                Some(asset) => asset.to_string(),
+                None => likely_asset(&release.assets, binary, manager)?,
+            };
+            release.asset_urls.get(&asset).cloned()
+        }
+    }
+}
+
+/// Configured binaries whose executable bina installed, with their install records
+fn managed_installs<'a>(state: &'a State, manager: &'a BinManager) -> Vec<(&'a Binary, &'a InstalledBinary)> {
+    manager
+        .data
+        .iter()
+        .filter_map(|(bin_name, binary)| {
+            let exe_path = exe_path(binary, manager);
+            let installed = state
+                .installed
+                .iter()
+                .find(|installed| installed.name == *bin_name && Path::new(&installed.path) == exe_path)?;
+            state.manages(bin_name, &exe_path).then_some((binary, installed))
+        })
+        .collect()
+}
+
+/// Every configured binary bina installed, with where it came from and the checksum of what's on disk
+pub async fn sbom_entries(manager: &BinManager) -> Result<Vec<SbomEntry>, BinaError> {
+    let state = read_state()?;
+    let lockfile = read_lockfile()?;
+    let client = &manager.client;
+    let mut entries: Vec<SbomEntry> = stream::iter(managed_installs(&state, manager))
+        .map(|(binary, installed)| {
+            let client = &client;
+            let locked_asset = lockfile
+                .binaries
+                .iter()
+                .find(|locked| locked.name == binary.name && locked.repo == binary.repo && locked.tag == installed.tag)
+                .and_then(|locked| locked.asset.as_deref());
+            async move {
+                SbomEntry {
+                    name: binary.name.clone(),
+                    version: parse_version(&installed.tag, binary, manager).unwrap_or(installed.tag.clone()),
+                    tag: installed.tag.clone(),
+                    repo: binary.repo.clone(),
+                    source_url: repo_web_url(binary),
+                    download_url: download_url(client, binary, &installed.tag, locked_asset, manager).await,
+                    sha256: installed.sha256.clone(),
+                }
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_REQUESTS)
+        .collect()
+        .await;
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Known vulnerability affecting an installed binary, as reported by `audit`
+#[derive(Debug, Serialize)]
+pub struct Vulnerability {
+    pub binary: String,
+    pub version: String,
+    /// OSV identifier, e.g. GHSA-xxxx-xxxx-xxxx or RUSTSEC-2024-0001
+    pub id: String,
+    pub summary: Option<String>,
+    /// Oldest release that fixes it, when OSV knows one
+    pub fixed: Option<String>,
+}
+
+const OSV_QUERY_URL: &str = "https://api.osv.dev/v1/query";
+
+/// Asks OSV.dev which vulnerabilities affect the installed version of each binary bina manages
+///
+/// crates.io builds are looked up by crate and version, everything else by repository and tag. Binaries whose
+/// query fails are recorded in `summary` and don't keep the rest from being audited.
+pub async fn audit_binaries(manager: &BinManager, summary: &mut BatchSummary) -> Result<Vec<Vulnerability>, BinaError> {
+    let state = read_state()?;
+    let client = &manager.client;
+    let results: Vec<(&str, Result<Vec<Vulnerability>, BinaError>)> = stream::iter(managed_installs(&state, manager))
+        .map(|(binary, installed)| {
+            let client = &client;
+            let result = async move {
+                let version = parse_version(&installed.tag, binary, manager).unwrap_or(installed.tag.clone());
+                let query = match binary.source {
+                    Source::CratesIo => serde_json::json!({
+                        "package": { "name": binary.repo, "ecosystem": "crates.io" },
+                        "version": version,
+                    }),
+                    Source::Release => serde_json::json!({
+                        "package": { "name": repo_web_url(binary), "ecosystem": "GIT" },
+                        "version": installed.tag,
+                    }),
+                };
+                debug!("Querying OSV for {} {}", binary.name, installed.tag);
+                let request = client.post(OSV_QUERY_URL).json(&query);
+                let json: Value = send_with_retries(request, manager)
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                let installed_version = Version::parse(&version);
+                Ok(json["vulns"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|vuln| {
+                        Some(Vulnerability {
+                            binary: binary.name.clone(),
+                            version: version.clone(),
+                            id: vuln["id"].as_str()?.to_string(),
+                            summary: vuln["summary"].as_str().map(String::from),
+                            fixed: fixed_version(vuln, &installed_version),
+                        })
+                    })
+                    .collect())
+            };
+            async move { (binary.name.as_str(), result.await) }
+        })
+        .buffer_unordered(MAX_CONCURRENT_REQUESTS)
+        .collect()
+        .await;
+
+    let mut vulnerabilities = vec![];
+    for (bin_name, result) in results {
+        match result {
+            Ok(found) => vulnerabilities.extend(found),
+            Err(e) => summary.failed.push(format!("{}: audit failed: {}", bin_name, e)),
+        }
+    }
+    vulnerabilities.sort_by(|a, b| a.binary.cmp(&b.binary).then_with(|| a.id.cmp(&b.id)));
+    Ok(vulnerabilities)
+}
+
+/// Oldest `fixed` event of an OSV record that is newer than the installed version
+fn fixed_version(vuln: &Value, installed: &Version) -> Option<String> {
+    vuln["affected"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|affected| affected["ranges"].as_array().into_iter().flatten())
+        .flat_map(|range| range["events"].as_array().into_iter().flatten())
+        .filter_map(|event| event["fixed"].as_str())
+        .map(|fixed| (Version::parse(fixed), fixed))
+        .filter(|(version, _)| installed.is_outdated_by(version))
+        .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .map(|(_, fixed)| fixed.to_string())
+}
+
+/// Package URL identifying an SBOM entry, e.g. pkg:github/BurntSushi/ripgrep@14.1.0
+fn package_url(entry: &SbomEntry, binary: Option<&Binary>) -> String {
+    match binary.map(|binary| (binary.forge, binary.source, &binary.url)) {
+        Some((_, Source::CratesIo, _)) => format!("pkg:cargo/{}@{}", entry.repo, entry.version),
+        Some((Forge::GitHub, Source::Release, None)) => format!("pkg:github/{}@{}", entry.repo, entry.tag),
+        _ => format!("pkg:generic/{}@{}", entry.name, entry.version),
+    }
+}
+
+/// A CycloneDX 1.5 JSON document describing the binaries in `entries`
+pub fn cyclonedx_sbom(entries: &[SbomEntry], manager: &BinManager) -> Value {
+    let components: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            let mut references = vec![serde_json::json!({ "type": "vcs", "url": entry.source_url })];
+            if let Some(download_url) = &entry.download_url {
+                references.push(serde_json::json!({ "type": "distribution", "url": download_url }));
+            }
+            let mut component = serde_json::json!({
+                "type": "application",
+                "bom-ref": entry.name,
+                "name": entry.name,
+                "version": entry.version,
+                "purl": package_url(entry, manager.data.get(&entry.name)),
+                "externalReferences": references,
+            });
+            // Entries recorded before bina kept checksums have none to offer
+            if !entry.sha256.is_empty() {
+                component["hashes"] = serde_json::json!([{ "alg": "SHA-256", "content": entry.sha256 }]);
+            }
+            component
+        })
+        .collect();
+    serde_json::json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": rfc3339(unix_time()),
+            "tools": {
+                "components": [{ "type": "application", "name": "bina", "version": env!("CARGO_PKG_VERSION") }],
+            },
+        },
+        "components": components,
+    })
+}
+
+/// Formats seconds since the Unix epoch as a UTC timestamp such as 2024-05-01T12:00:00Z
+fn rfc3339(secs: u64) -> String {
+    // Days to a proleptic Gregorian date, after Howard Hinnant's days_from_civil inverse
+    let days = secs / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    let seconds = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Files published next to release assets that are never the asset itself
+const NON_ASSET_SUFFIXES: &[&str] = &[
+    ".sha256",
+    ".sha512",
+    ".sig",
+    ".asc",
+    ".minisig",
+    ".pem",
+    ".sigstore",
+    ".bundle",
+    ".intoto.jsonl",
+    ".txt",
+    ".sbom",
+    ".json",
+    ".deb",
+    ".rpm",
+];
+
+/// A bare `win`, `win32`, or `win64` token in an asset name
+static WIN_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(^|[-_.])win(32|64)?([-_.]|$)").expect("the win pattern is valid"));
+
+/// Whether an asset name mentions `os`; Windows builds are often marked by a bare `win` token, which is only
+/// accepted between delimiters so that it doesn't match every `darwin` asset
+fn names_os(name: &str, os: &str) -> bool {
+    match os {
+        "macos" => ["macos", "darwin", "apple", "osx"].iter().any(|os| name.contains(os)),
+        "windows" => !name.contains("darwin") && (name.contains("windows") || WIN_REGEX.is_match(name)),
+        os => name.contains(os),
+    }
+}
+
+/// The release asset that best fits the target platform, if any does
+fn likely_asset(assets: &[String], binary: &Binary, manager: &BinManager) -> Option<String> {
+    let arch_names: &[&str] = match manager.target_arch() {
+        "x86_64" => &["x86_64", "amd64", "x64"],
+        "aarch64" => &["aarch64", "arm64"],
+        arch => &[arch],
+    };
+    let matching_regex = binary
+        .matching_regex
+        .as_deref()
+        .and_then(|regex| Regex::new(regex).ok());
+    let mut candidates: Vec<&String> = assets
+        .iter()
+        .filter(|asset| {
+            let name = asset.to_lowercase();
+            names_os(&name, manager.target_os())
+                && arch_names.iter().any(|arch| name.contains(arch))
+                && !NON_ASSET_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+        })
+        .filter(|asset| matching_regex.as_ref().is_none_or(|regex| regex.is_match(asset)))
+        .collect();
+    if let Some(matching) = &binary.matching {
+        candidates.sort_by_key(|asset| !asset.contains(matching.as_str()));
+    } else if let Some(libc) = binary.libc {
+        candidates.sort_by_key(|asset| !asset.contains(libc.as_str()));
+    }
+    candidates.first().map(|asset| asset.to_string())
+}
+
+/// Reports which binaries are installed, optionally with their latest releases
+pub async fn check_binaries(manager: &BinManager, check_latest: bool, patterns: &[Pattern]) -> Vec<BinaryStatus> {
+    if ensure_bin_directory(&manager.xdg_bin_home, manager.dry_run).is_err() {
+        return vec![];
+    }
+
+    let state = read_state().unwrap_or_else(|e| {
+        warn!("Ignoring unreadable state: {}", e);
+        State::default()
+    });
+
+    let client = &manager.client;
+    let selected: Vec<(&String, &Binary)> = manager
+        .data
+        .iter()
+        .filter(|(_, binary)| manager.selects(binary))
+        .filter(|(bin_name, _)| patterns.is_empty() || patterns.iter().any(|pattern| pattern.matches(bin_name)))
+        .collect();
+    let batched_tags = if check_latest {
+        let wanted: Vec<&Binary> = selected
+            .iter()
+            .filter(|(bin_name, binary)| binary.version.is_none() && !state.is_held(bin_name))
+            .map(|(_, binary)| *binary)
+            .collect();
+        batch_latest_tags(&wanted, manager).await
+    } else {
+        HashMap::new()
+    };
+    let statuses = stream::iter(selected)
+        .map(|(bin_name, binary)| {
+            let client = &client;
+            let batched_tag = batched_tags.get(bin_name);
+            let held = state.is_held(bin_name);
+            let exe_path = exe_path(binary, manager);
+            let managed = state.manages(bin_name, &exe_path);
+            async move {
+                let installed = exe_path.exists();
+                let version = if installed && is_executable(&exe_path) {
+                    current_version(binary, manager)
+                } else {
+                    None
+                };
+                let latest = match batched_tag {
+                    _ if !check_latest || binary.version.is_some() || held => None,
+                    Some(tag) => Some(parse_version(tag, binary, manager).unwrap_or(tag.clone())),
+                    None => latest_version(client, binary, manager).await,
+                };
+                BinaryStatus {
+                    binary: bin_name.to_string(),
+                    installed,
+                    version,
+                    latest,
+                    pinned: binary.version.clone(),
+                    held,
+                    managed: installed && managed,
+                    not_executable: installed && !is_executable(&exe_path),
+                }
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_REQUESTS)
+        .collect::<Vec<_>>()
+        .await;
+    save_probes(manager);
+    statuses
+}
+
+/// What a batch command did to each binary, reported once it's done
+pub struct BatchSummary {
+    /// Installed binaries with their tags
+    pub installed: Vec<String>,
+    /// Upgraded binaries with their old and new versions
+    pub upgraded: Vec<String>,
+    /// Binaries left alone on purpose, with the reason
+    pub skipped: Vec<String>,
+    /// Binaries that failed, with the error
+    pub failed: Vec<String>,
+    /// Binaries that were already where they should be
+    pub unchanged: usize,
+    dry_run: bool,
+    started: Instant,
+    /// Binaries a step already handled, which later steps of the same run leave alone
+    handled: HashSet<String>,
+}
+
+impl BatchSummary {
+    pub fn new(dry_run: bool) -> BatchSummary {
+        BatchSummary {
+            installed: vec![],
+            upgraded: vec![],
+            skipped: vec![],
+            failed: vec![],
+            unchanged: 0,
+            dry_run,
+            started: Instant::now(),
+            handled: HashSet::new(),
+        }
+    }
+
+    /// A block listing what happened to each binary, ending with the counts and the time taken
+    pub fn report(&self) -> String {
+        let [installed, upgraded, installed_count, upgraded_count] = if self.dry_run {
+            ["Would install", "Would upgrade", "to install", "to upgrade"]
+        } else {
+            ["Installed", "Upgraded", "installed", "upgraded"]
+        };
+        let mut lines = vec![];
+        for (label, entries) in [
+            (installed, &self.installed),
+            (upgraded, &self.upgraded),
+            ("Skipped", &self.skipped),
+            ("Failed", &self.failed),
+        ] {
+            if entries.is_empty() {
+                continue;
+            }
+            // Concurrent installs finish in any order
+            let mut entries = entries.clone();
+            entries.sort();
+            lines.push(format!("{} {}:", label, entries.len()));
+            lines.extend(entries.iter().map(|entry| format!("  {}", entry)));
+        }
+        let counts: Vec<String> = [
+            (self.installed.len(), installed_count),
+            (self.upgraded.len(), upgraded_count),
+            (self.unchanged, "unchanged"),
+            (self.skipped.len(), "skipped"),
+            (self.failed.len(), "failed"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect();
+        lines.push(format!(
+            "{} in {:.1}s",
+            if counts.is_empty() {
+                "Nothing to do".to_string()
+            } else {
+                counts.join(", ")
+            },
+            self.started.elapsed().as_secs_f64()
+        ));
+        lines.join("\n")
+    }
+}
+
+/// Installs every selected binary that isn't installed yet
+pub async fn get_missing_binaries(
+    manager: &BinManager,
+    locked: bool,
+    jobs: usize,
+    summary: &mut BatchSummary,
+) -> Result<(), BinaError> {
+    summary.unchanged += manager
+        .data
+        .values()
+        .filter(|binary| manager.selects(binary) && exe_path(binary, manager).exists())
+        .count();
+    install_missing(manager, locked, jobs, summary).await
+}
+
+async fn install_missing(
+    manager: &BinManager,
+    locked: bool,
+    jobs: usize,
+    summary: &mut BatchSummary,
+) -> Result<(), BinaError> {
+    ensure_bin_directory(&manager.xdg_bin_home, manager.dry_run)?;
+
+    let not_found: Vec<&String> = manager
+        .data
+        .iter()
+        .filter(|(_, binary)| !exe_path(binary, manager).exists() && manager.selects(binary))
+        .map(|(bin_name, _)| bin_name)
+        .collect();
+
+    let mut results = stream::iter(not_found)
+        .map(|bin_name| async move {
+            let result = get_binary(bin_name, manager, locked, None).await;
+            (bin_name, result)
+        })
+        .buffer_unordered(jobs.max(1));
+
+    while let Some((bin_name, result)) = results.next().await {
+        summary.handled.insert(bin_name.clone());
+        match result {
+            Ok(tag) => summary.installed.push(format!("{} {}", bin_name, tag)),
+            // Dropping the stream cancels the installs still in flight
+            Err(e) if manager.fail_fast => {
+                summary.failed.push(format!("{}: {}", bin_name, e));
+                return Err(format!("Failed to download {}: {}", bin_name, e).into());
+            }
+            Err(e) => summary.failed.push(format!("{}: {}", bin_name, e)),
+        }
+    }
+    Ok(())
+}
+
+/// Upgrades installed binaries whose version differs from their target release
+pub async fn update_binaries(
+    manager: &BinManager,
+    show_notes: bool,
+    summary: &mut BatchSummary,
+) -> Result<(), BinaError> {
+    ensure_bin_directory(&manager.xdg_bin_home, manager.dry_run)?;
+
+    let installed: Vec<&String> = manager
+        .data
+        .iter()
+        .filter(|(bin_name, binary)| {
+            exe_path(binary, manager).exists() && manager.selects(binary) && !summary.handled.contains(*bin_name)
+        })
+        .map(|(bin_name, _)| bin_name)
+        .collect();
+
+    let state = read_state()?;
+    let client = &manager.client;
+    let mut notes = vec![];
+    for bin_name in installed {
+        if state.is_held(bin_name) {
+            summary.skipped.push(format!(
+                "{}: held after a rollback, run `bina get {}` to release it",
+                bin_name, bin_name
+            ));
+            continue;
+        }
+        let binary = &manager.data[bin_name];
+        let Some(current) = current_version(binary, manager) else {
+            summary
+                .skipped
+                .push(format!("{}: could not determine installed version", bin_name));
+            continue;
+        };
+        let latest = match &binary.version {
+            Some(version) => parse_version(version, binary, manager).unwrap_or(version.clone()),
+            None => {
+                let Some(latest) = latest_version(client, binary, manager).await else {
+                    summary
+                        .skipped
+                        .push(format!("{}: could not fetch latest release", bin_name));
+                    continue;
+                };
+                latest
+            }
+        };
+        if !Version::parse(&current).is_outdated_by(&Version::parse(&latest)) {
+            summary.unchanged += 1;
+            continue;
+        }
+
+        info!("Updating {} {} -> {}...", bin_name, current, latest);
+        if let Err(e) = get_binary(bin_name, manager, false, None).await {
+            summary.failed.push(format!("{}: {}", bin_name, e));
+            if manager.fail_fast {
+                return Err(e);
+            }
+            continue;
+        }
+        summary.upgraded.push(format!("{} {} -> {}", bin_name, current, latest));
+        if show_notes && let Some(release_notes) = release_notes(client, binary, manager, &current, &latest).await {
+            notes.push(release_notes);
+        }
+    }
+
+    save_probes(manager);
+    if !notes.is_empty() {
+        page(&notes.join("\n"));
+    }
+    Ok(())
+}
+
+/// Shows a desktop notification listing new releases
+///
+/// A failed notification only gets a warning, since the same news is logged anyway.
+pub fn notify_updates(updates: &[String]) {
+    if updates.is_empty() {
+        return;
+    }
+    let summary = match updates.len() {
+        1 => "1 new release".to_string(),
+        count => format!("{} new releases", count),
+    };
+    if let Err(e) = Notification::new()
+        .appname("bina")
+        .summary(&summary)
+        .body(&updates.join("\n"))
+        .show()
+    {
+        warn!("Failed to send a notification: {}", e);
+    }
+}
+
+/// Checks for new releases every `interval` until interrupted, logging each new version once and
+/// installing it right away for binaries with `auto_update` set; `notify` also announces them on the desktop
+pub async fn watch_releases(manager: &BinManager, interval: Duration, notify: bool) -> Result<(), BinaError> {
+    ensure_bin_directory(&manager.xdg_bin_home, manager.dry_run)?;
+    let client = &manager.client;
+    let mut reported: HashSet<(String, String)> = HashSet::new();
+    loop {
+        debug!("Checking for new releases");
+        let state = read_state().unwrap_or_else(|e| {
+            warn!("Ignoring unreadable state: {}", e);
+            State::default()
+        });
+        let mut found = vec![];
+        for (bin_name, binary) in &manager.data {
+            // Pinned and held binaries stay where they are until the user moves them
+            if !manager.selects(binary) || binary.version.is_some() || state.is_held(bin_name) {
+                continue;
+            }
+            if !exe_path(binary, manager).exists() {
+                continue;
+            }
+            let Some(current) = current_version(binary, manager) else {
+                continue;
+            };
+            let Some(latest) = latest_version(client, binary, manager).await else {
+                warn!("Could not fetch the latest release of {}", bin_name);
+                continue;
+            };
+            if !Version::parse(&current).is_outdated_by(&Version::parse(&latest)) {
+                continue;
+            }
+            if !binary.auto_update {
+                if reported.insert((bin_name.clone(), latest.clone())) {
+                    info!("{} {} -> {} is available", bin_name, current, latest);
+                    found.push(format!("{} {} -> {}", bin_name, current, latest));
+                }
+                continue;
+            }
+            info!("Updating {} {} -> {}...", bin_name, current, latest);
+            match get_binary(bin_name, manager, false, None).await {
+                Ok(_) => {
+                    info!("Updated {} {} -> {}", bin_name, current, latest);
+                    found.push(format!("{} {} -> {} (installed)", bin_name, current, latest));
+                }
+                Err(e) => warn!("Failed to update {}: {}", bin_name, e),
+            }
+        }
+        save_probes(manager);
+        if notify {
+            notify_updates(&found);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Executables bina installed that no config entry claims anymore; binaries whose entries were removed also
+/// lose their stored versions
+///
+/// Only lists them unless `delete` is set. Files in XDG_BIN_HOME that bina did not install get a warning and are
+/// never deleted.
+pub fn prune_binaries(manager: &BinManager, delete: bool) -> Result<String, BinaError> {
+    ensure_bin_directory(&manager.xdg_bin_home, manager.dry_run)?;
+    let configured: Vec<String> = manager
+        .data
+        .values()
+        .filter(|binary| binary.install_dir.is_none())
+        .flat_map(|binary| binary.exe.iter().chain(&binary.aliases))
+        .map(|exe| exe_file_name(exe, manager.target_os()))
+        .collect();
+    // Never prune the running bina executable when it lives in XDG_BIN_HOME
+    let own_exe = env::current_exe()
+        .ok()
+        .and_then(|path| path.file_name().and_then(|name| name.to_str()).map(String::from));
+
+    let mut state = read_state()?;
+    // Paths paired with the binary bina installed there when its entry is gone, so its store goes too
+    let mut prunable: Vec<(PathBuf, Option<String>)> = vec![];
+    let mut foreign: Vec<PathBuf> = vec![];
+    for file_name in installed_binaries(&manager.xdg_bin_home) {
+        if configured.contains(&file_name) || own_exe.as_ref() == Some(&file_name) {
+            continue;
+        }
+        let path = Path::new(&manager.xdg_bin_home).join(&file_name);
+        if !path.is_file() {
+            continue;
+        }
+        let owner = state
+            .installed
+            .iter()
+            .find(|installed| Path::new(&installed.path) == path)
+            .map(|installed| installed.name.clone())
+            .or_else(|| {
+                // Installs from before paths were recorded are still links into the store
+                linked_tag(&path)?;
+                let target = fs::read_link(&path).ok()?;
+                target.parent()?.parent()?.file_name()?.to_str().map(String::from)
+            });
+        match owner {
+            Some(name) => prunable.push((path, Some(name).filter(|name| !manager.data.contains_key(name)))),
+            None => foreign.push(path),
+        }
+    }
+    foreign.sort();
+    for path in &foreign {
+        warn!(
+            "Leaving {} in place: bina did not install it, delete it by hand if it should go",
+            path.display()
+        );
+    }
+    // Binaries with their own install_dir live outside XDG_BIN_HOME
+    for installed in &state.installed {
+        let path = PathBuf::from(&installed.path);
+        if !manager.data.contains_key(&installed.name)
+            && path.is_file()
+            && !prunable.iter().any(|(prunable_path, _)| *prunable_path == path)
+        {
+            prunable.push((path, Some(installed.name.clone())));
+        }
+    }
+
+    if prunable.is_empty() {
+        return Ok("No binaries to prune.".to_string());
+    }
+    prunable.sort();
+    let lines: Vec<String> = prunable
+        .iter()
+        .map(|(path, owner)| match owner {
+            Some(name) => format!("{} (installed as {}, no longer configured)", path.display(), name),
+            None => format!(
+                "{} (installed by bina, no longer one of its executables)",
+                path.display()
+            ),
+        })
+        .collect();
+    if !delete {
+        return Ok(format!(
+            "{} binaries to prune, run `bina prune --yes` to remove them:\n  {}",
+            prunable.len(),
+            lines.join("\n  ")
+        ));
+    }
+
+    for (path, owner) in &prunable {
+        if manager.dry_run {
+            info!("Would remove {}", path.display());
+            continue;
+        }
+        fs::remove_file(path)?;
+        info!("Removed {}", path.display());
+        if let Some(name) = owner {
+            let store = binary_store(name);
+            if store.exists() {
+                fs::remove_dir_all(&store)?;
+                debug!("Removed stored versions in {}", store.display());
+            }
+            state.installed.retain(|installed| installed.name != *name);
+            state.held.retain(|held| held.name != *name);
+        }
+    }
+    if !manager.dry_run {
+        write_state(&state)?;
+    }
+    Ok(format!(
+        "{} {} binaries:\n  {}",
+        if manager.dry_run { "Would prune" } else { "Pruned" },
+        prunable.len(),
+        lines.join("\n  ")
+    ))
+}
+
+/// Installs missing binaries, then upgrades the rest, recording both in `summary`
+pub async fn sync_binaries(manager: &BinManager, jobs: usize, summary: &mut BatchSummary) -> Result<(), BinaError> {
+    // Failed installs are in the summary and don't keep the remaining binaries from being upgraded
+    install_missing(manager, false, jobs, summary).await?;
+    update_binaries(manager, false, summary).await
+}
+
+/// Outcome of one `doctor` check, with a suggested fix when it failed
+pub struct Diagnosis {
+    pub message: String,
+    pub fix: Option<String>,
+}
+
+impl Diagnosis {
+    /// A check that passed
+    pub fn ok(message: impl Into<String>) -> Diagnosis {
+        Diagnosis {
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    /// A failed check with a suggested fix
+    pub fn problem(message: impl Into<String>, fix: impl Into<String>) -> Diagnosis {
+        Diagnosis {
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Parses one config file strictly, recording syntax errors, unknown keys, bad repos, and duplicate names
+fn validate_config_file(path: &Path, diagnoses: &mut Vec<Diagnosis>) -> Option<Config> {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            diagnoses.push(Diagnosis::problem(
+                format!("Cannot read {}: {}", path.display(), e),
+                "check the path and its permissions",
+            ));
+            return None;
+        }
+    };
+    let mut unknown_keys = vec![];
+    let parsed = toml::Deserializer::parse(&raw)
+        .and_then(|document| serde_ignored::deserialize(document, |key| unknown_keys.push(key.to_string())));
+    let config: Config = match parsed {
+        Ok(config) => config,
+        Err(e) => {
+            diagnoses.push(Diagnosis::problem(
+                format!("{} does not parse: {}", path.display(), e.message()),
+                "fix the TOML syntax or the type of the value",
+            ));
+            return None;
+        }
+    };
+
+    let problems = diagnoses.len();
+    for key in unknown_keys {
+        diagnoses.push(Diagnosis::problem(
+            format!("Unknown key `{}` in {}", key, path.display()),
+            "remove it or correct the spelling",
+        ));
+    }
+    for binary in &config.binaries {
+        if let Some(mode) = binary.mode.as_deref().filter(|mode| parse_mode(mode).is_none()) {
+            diagnoses.push(Diagnosis::problem(
+                format!("{} in {} has an invalid mode \"{}\"", binary.name, path.display(), mode),
+                "use octal permissions such as \"0755\"",
+            ));
+        }
+        for pattern in &binary.only_hosts {
+            if let Err(e) = Pattern::new(pattern) {
+                diagnoses.push(Diagnosis::problem(
+                    format!(
+                        "{} in {} has an invalid only_hosts pattern \"{}\": {}",
+                        binary.name,
+                        path.display(),
+                        pattern,
+                        e
+                    ),
+                    "fix the glob syntax",
+                ));
+            }
+        }
+        if let Some(Err(e)) = binary.cosign_identity.as_deref().map(Regex::new) {
+            diagnoses.push(Diagnosis::problem(
+                format!(
+                    "{} in {} has an invalid cosign_identity: {}",
+                    binary.name,
+                    path.display(),
+                    e
+                ),
+                "fix the regex syntax",
+            ));
+        }
+        if let Some(Err(e)) = binary.version_regex.as_deref().map(Regex::new) {
+            diagnoses.push(Diagnosis::problem(
+                format!(
+                    "{} in {} has an invalid version_regex: {}",
+                    binary.name,
+                    path.display(),
+                    e
+                ),
+                "fix the regex syntax",
+            ));
+        }
+        if binary.require_attestation && (binary.forge != Forge::GitHub || binary.source == Source::CratesIo) {
+            diagnoses.push(Diagnosis::problem(
+                format!(
+                    "{} in {} sets require_attestation without being a GitHub release",
+                    binary.name,
+                    path.display()
+                ),
+                "remove require_attestation, which only GitHub release assets support",
+            ));
+        }
+        if binary.sha256.is_some() && binary.version.is_none() {
+            diagnoses.push(Diagnosis::problem(
+                format!("{} in {} sets sha256 without a version", binary.name, path.display()),
+                "pin the release the digest belongs to with version",
+            ));
+        }
+        if let Some(expected) = malformed_repo(binary) {
+            diagnoses.push(Diagnosis::problem(
+                format!("{} in {} has repo \"{}\"", binary.name, path.display(), binary.repo),
+                format!("set repo to {}", expected),
+            ));
+        }
+    }
+    let mut names = HashSet::new();
+    for binary in &config.binaries {
+        if !names.insert(binary.name.as_str()) {
+            diagnoses.push(Diagnosis::problem(
+                format!("{} is defined more than once in {}", binary.name, path.display()),
+                "merge the entries or rename one of them",
+            ));
+        }
+    }
+    if diagnoses.len() == problems {
+        diagnoses.push(Diagnosis::ok(format!("{} is valid", path.display())));
+    }
+    Some(config)
+}
+
+/// Describes the expected repo form when `binary.repo` doesn't fit its source and forge
+fn malformed_repo(binary: &Binary) -> Option<&'static str> {
+    let parts: Vec<&str> = binary.repo.split('/').collect();
+    let well_formed = parts
+        .iter()
+        .all(|part| !part.is_empty() && !part.contains(char::is_whitespace));
+    match (&binary.source, binary.forge) {
+        (Source::CratesIo, _) => (!well_formed || parts.len() != 1).then_some("the crate name"),
+        (Source::Release, Forge::GitLab) => {
+            (!well_formed || parts.len() < 2).then_some("the project path, e.g. group/subgroup/name")
+        }
+        (Source::Release, _) => (!well_formed || parts.len() != 2).then_some("owner/name"),
+    }
+}
+
+/// Validates binaries.toml and its includes, then checks the merged config for executable collisions
+pub fn validate_config() -> Vec<Diagnosis> {
+    let toml_path = config_path();
+    let mut diagnoses = vec![];
+    if !Path::new(&toml_path).exists() {
+        diagnoses.push(Diagnosis::ok(format!(
+            "No config at {}, only built-in binaries are used",
+            toml_path
+        )));
+        return diagnoses;
+    }
+    let Some(config) = validate_config_file(Path::new(&toml_path), &mut diagnoses) else {
+        return diagnoses;
+    };
+    match included_config_files(&toml_path, &config.include) {
+        Ok(paths) => {
+            for path in paths {
+                validate_config_file(&path, &mut diagnoses);
+            }
+        }
+        Err(e) => diagnoses.push(Diagnosis::problem(
+            e.to_string(),
+            "create the file or drop it from include",
+        )),
+    }
+
+    // Different entries writing the same executable would overwrite each other
+    let Ok(merged) = load_config() else {
+        return diagnoses;
+    };
+    let mut targets: HashMap<(Option<&str>, &str), &str> = HashMap::new();
+    for binary in &merged.binaries {
+        for exe in &binary.exe {
+            if let Some(other) = targets.insert((binary.install_dir.as_deref(), exe), &binary.name)
+                && other != binary.name
+            {
+                diagnoses.push(Diagnosis::problem(
+                    format!("{} and {} both install the executable {}", other, binary.name, exe),
+                    "give one of them a different exe or install_dir",
+                ));
+            }
+        }
+    }
+    diagnoses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pre_releases_follow_semver_precedence() {
+        // The example chain from the semver spec, each entry below the next
+        let chain = [
+            Some("alpha"),
+            Some("alpha.1"),
+            Some("alpha.beta"),
+            Some("beta"),
+            Some("beta.2"),
+            Some("beta.11"),
+            Some("rc.1"),
+            None,
+        ];
+        for pair in chain.windows(2) {
+            assert_eq!(
+                compare_pre(pair[0], pair[1]),
+                Ordering::Less,
+                "{:?} < {:?}",
+                pair[0],
+                pair[1]
+            );
+            assert_eq!(
+                compare_pre(pair[1], pair[0]),
+                Ordering::Greater,
+                "{:?} > {:?}",
+                pair[1],
+                pair[0]
+            );
+        }
+        assert_eq!(compare_pre(Some("rc.1"), Some("rc.1")), Ordering::Equal);
+    }
+
+    /// A manager for `os` and `arch` with no config, network, or state behind it
+    fn manager_for(os: &str, arch: &str) -> BinManager {
+        BinManager {
+            data: BTreeMap::new(),
+            xdg_bin_home: String::new(),
+            regex: Regex::new(r"(\d+(?:\.\d+)+)").unwrap(),
+            github_token: None,
+            gitlab_token: None,
+            forgejo_token: None,
+            progress: MultiProgress::new(),
+            dry_run: true,
+            target_os: Some(os.to_string()),
+            target_arch: Some(arch.to_string()),
+            tags: vec![],
+            host: None,
+            profile: None,
+            offline_assets: None,
+            strip_quarantine: false,
+            fail_fast: false,
+            github_api_base_url: Forge::GitHub.default_api_base_url().to_string(),
+            rate_limit: None,
+            http_timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            retry: RetryPolicy {
+                attempts: 1,
+                backoff: Duration::ZERO,
+                jitter: false,
+            },
+            client: Client::new(),
+            proxy: None,
+            probes: Mutex::new(None),
+        }
+    }
+
+    #[test]
+    fn versions_parse_by_scheme() {
+        assert!(matches!(Version::parse("v1.2.3"), Version::Semver { parts, pre: None } if parts == [1, 2, 3]));
+        assert!(matches!(Version::parse("1.0.0-rc.1+build"), Version::Semver { pre: Some(pre), .. } if pre == "rc.1"));
+        assert!(matches!(Version::parse("nightly-2024-05-01"), Version::Calver(parts) if parts == [2024, 5, 1]));
+        assert!(matches!(Version::parse("20240501"), Version::Calver(parts) if parts == [2024, 5, 1]));
+        assert!(matches!(Version::parse("2024.05.01"), Version::Calver(parts) if parts == [2024, 5, 1]));
+        assert!(matches!(Version::parse("abc1234"), Version::Opaque(hash) if hash == "abc1234"));
+
+        assert!(Version::parse("1.2").is_outdated_by(&Version::parse("1.10")));
+        assert!(!Version::parse("1.2").is_outdated_by(&Version::parse("1.2.0")));
+        assert!(Version::parse("1.0.0-rc.1").is_outdated_by(&Version::parse("1.0.0")));
+        assert!(Version::parse("abc1234").is_outdated_by(&Version::parse("def5678")));
+    }
+
+    #[test]
+    fn env_references_expand() {
+        let home = env::var("HOME").unwrap();
+        assert_eq!(expand_env("~/bin").unwrap(), format!("{}/bin", home));
+        assert_eq!(expand_env("${HOME}/x-$HOME").unwrap(), format!("{}/x-{}", home, home));
+        assert_eq!(expand_env("~user").unwrap(), "~user");
+        assert_eq!(expand_env("$$HOME").unwrap(), "$HOME");
+        assert_eq!(expand_env("^v[0-9]+$").unwrap(), "^v[0-9]+$");
+        assert!(expand_env("${HOME").is_err());
+        assert!(expand_env("$BINA_TEST_SURELY_UNSET").is_err());
+    }
+
+    #[test]
+    fn rates_and_ages_parse() {
+        assert_eq!(parse_rate("500"), Ok(500));
+        assert_eq!(parse_rate("2M"), Ok(2 << 20));
+        assert_eq!(parse_rate("1.5k"), Ok(1536));
+        assert!(parse_rate("0K").is_err());
+        assert!(parse_rate("5T").is_err());
+        assert!(parse_rate("fast").is_err());
+
+        assert_eq!(parse_age("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_age("30d"), Ok(Duration::from_secs(30 * 24 * 60 * 60)));
+        assert_eq!(parse_age("2w"), Ok(Duration::from_secs(14 * 24 * 60 * 60)));
+        assert!(parse_age("30").is_err());
+        assert!(parse_age("d").is_err());
+    }
+
+    #[test]
+    fn assets_fit_the_target_platform() {
+        let assets: Vec<String> = [
+            "fd-v10.1.0-aarch64-apple-darwin.tar.gz",
+            "fd-v10.1.0-x86_64-apple-darwin.tar.gz",
+            "fd-v10.1.0-x86_64-pc-windows-msvc.zip",
+            "fd-v10.1.0-x86_64-unknown-linux-gnu.tar.gz",
+            "fd-v10.1.0-x86_64-unknown-linux-gnu.tar.gz.sha512",
+            "fd-v10.1.0-x86_64-unknown-linux-musl.tar.gz",
+            "fd-v10.1.0-x86_64-unknown-linux-musl.tar.gz.sigstore.json",
+        ]
+        .map(String::from)
+        .to_vec();
+        let binary = Binary::default();
+        let pick = |binary: &Binary, os: &str, arch: &str| likely_asset(&assets, binary, &manager_for(os, arch));
+
+        assert_eq!(
+            pick(&binary, "macos", "aarch64").as_deref(),
+            Some("fd-v10.1.0-aarch64-apple-darwin.tar.gz")
+        );
+        assert_eq!(
+            pick(&binary, "windows", "x86_64").as_deref(),
+            Some("fd-v10.1.0-x86_64-pc-windows-msvc.zip")
+        );
+        assert_eq!(
+            pick(&binary, "linux", "x86_64").as_deref(),
+            Some("fd-v10.1.0-x86_64-unknown-linux-gnu.tar.gz")
+        );
+        let musl = Binary {
+            libc: Some(Libc::Musl),
+            ..Default::default()
+        };
+        assert_eq!(
+            pick(&musl, "linux", "x86_64").as_deref(),
+            Some("fd-v10.1.0-x86_64-unknown-linux-musl.tar.gz")
+        );
+        assert_eq!(pick(&binary, "linux", "aarch64"), None);
+
+        // `win` only counts as a token of its own, never inside darwin
+        let darwin = vec!["tool-darwin-x64.tar.gz".to_string(), "tool-win-x64.zip".to_string()];
+        assert_eq!(
+            likely_asset(&darwin, &binary, &manager_for("windows", "x86_64")).as_deref(),
+            Some("tool-win-x64.zip")
+        );
+    }
+}
@@ -1,26 +1,156 @@
-use clap::{Parser, Subcommand};
-use regex::Regex;
+use bina::{
+    BatchSummary, BinManager, BinaError, Binary, BinaryInfo, BinaryStatus, Diagnosis, ImportFormat, RateLimit,
+    SbomEntry, SearchResult, Version, Vulnerability, WhichInfo, add_binary, audit_binaries, bin_home, binary_info,
+    cache_size, check_binaries, clean_cache, config_path, current_version, cyclonedx_sbom, exe_path, export_manifest,
+    get_binary, get_missing_binaries, import_binaries, init_config, install_schedule, is_remote_config, latest_version,
+    link_config_directory, load_config, local_config_path, new_bin_manager, notify_updates, parse_age, parse_rate,
+    parse_version, prompt, prune_binaries, read_config_file, release_hold, remove_binary, remove_schedule,
+    rollback_binary, run_binary, sbom_entries, search_repositories, self_update, send_with_retries, set_config_path,
+    sync_binaries, update_binaries, use_remote_config, validate_config, watch_releases, which_binary,
+};
+use clap::builder::PossibleValuesParser;
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use comfy_table::{CellAlignment, TableComponent, presets};
+use futures::stream::{FuturesUnordered, StreamExt};
+use glob::Pattern;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use serde_json::Value;
-use std::collections::HashMap;
 use std::env;
-use std::fs::{self, DirEntry};
-use std::path::Path;
-use std::process::Command;
-use ubi::UbiBuilder;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, ExitCode};
+use std::time::Duration;
+use tracing::{info, warn};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::prelude::*;
+
+/// Number of binaries downloaded concurrently by batch installs
+const DEFAULT_JOBS: usize = 4;
 
 #[derive(Parser)]
 #[command(
     name = "bina",
     about = "Manages binary installations in XDG_BIN_HOME",
-    version = "0.2.0"
+    after_help = "Without a command, bina runs `bina check`.",
+    version
 )]
 struct Cli {
+    /// Config file to use instead of $XDG_CONFIG_HOME/bina/binaries.toml
+    #[arg(short, long, global = true, env = "BINA_CONFIG")]
+    config: Option<String>,
+    /// SHA-256 the config must have when --config is a URL
+    #[arg(long, global = true, env = "BINA_CONFIG_SHA256")]
+    config_sha256: Option<String>,
+    /// Show more detail; repeat for trace output
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+    /// Show less output; repeat to only show errors
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    quiet: u8,
+    /// Print what would change without downloading, writing, or deleting anything
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Output format for check, list, and sbom
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+    /// Download binaries for this architecture instead of the host's, e.g. aarch64
+    #[arg(long, global = true)]
+    target_arch: Option<String>,
+    /// Download binaries for this OS instead of the host's, e.g. linux
+    #[arg(long, global = true)]
+    target_os: Option<String>,
+    /// Cap download bandwidth, e.g. 500K or 2M bytes per second; overrides limit_rate in the config
+    #[arg(long, global = true, value_parser = parse_rate)]
+    limit_rate: Option<u64>,
+    /// When to color table output; NO_COLOR disables auto-detected color
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    /// Draw borders around tables
+    #[arg(long, global = true)]
+    borders: bool,
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+/// Order of the rows `check` prints; ties go by name
+#[derive(Clone, Copy, ValueEnum)]
+enum SortKey {
+    Name,
+    /// Missing first, then not executable, unmanaged, and installed
+    Status,
+    /// Lowest installed version first, missing versions before all others
+    Version,
+    /// Binaries behind their latest release first
+    Outdated,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+    Yaml,
+    /// CycloneDX JSON, for sbom
+    Cyclonedx,
+}
+
+/// Tag filter shared by the commands that act on many binaries
+#[derive(Args)]
+struct TagFilter {
+    /// Only act on binaries carrying one of these tags
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Reports unknown keys, malformed repos, and duplicate names or executables
+    Validate,
+    /// Opens binaries.toml in $VISUAL or $EDITOR and validates it afterwards
+    Edit,
+}
+
+#[derive(Subcommand)]
+enum CacheCommand {
+    /// Deletes cached downloads and metadata
+    Clean {
+        /// Only delete files last written longer ago than this, e.g. 30d, 12h, or 2w
+        #[arg(long, value_name = "AGE", value_parser = parse_age)]
+        older_than: Option<Duration>,
+    },
+    /// Reports the disk space the cache takes
+    Size,
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommand {
+    /// Writes and enables a systemd user service and timer
+    Install {
+        /// How often to run, as a systemd calendar expression such as daily or weekly
+        #[arg(long, default_value = "weekly")]
+        interval: String,
+        /// Run `bina update` instead of only checking for new releases
+        #[arg(long)]
+        update: bool,
+    },
+    /// Disables and deletes the service and timer
+    Remove,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Checks availability of binaries in XDG_BIN_HOME
@@ -28,401 +158,1270 @@ enum Commands {
         /// Check the latest release version from GitHub
         #[arg(long)]
         latest: bool,
+        /// Show a desktop notification listing outdated binaries; needs --latest or a flag implying it
+        #[arg(long)]
+        notify: bool,
+        /// Exit with code 10 if any checked binary is not installed
+        #[arg(long)]
+        fail_if_missing: bool,
+        /// Exit with code 10 if any checked binary is behind its latest release; implies --latest
+        #[arg(long)]
+        fail_if_outdated: bool,
+        /// Only show binaries that are behind their latest release; implies --latest
+        #[arg(long)]
+        outdated_only: bool,
+        /// Order of the rows
+        #[arg(long, value_enum, default_value_t = SortKey::Name)]
+        sort: SortKey,
+        /// Reverse the sort order
+        #[arg(long)]
+        reverse: bool,
+        /// Only check binaries whose names match these names or glob patterns
+        names: Vec<String>,
+        #[command(flatten)]
+        filter: TagFilter,
+    },
+    /// Lists configured binaries without checking versions
+    List {
+        #[command(flatten)]
+        filter: TagFilter,
+    },
+    /// Shows the configuration, installed version, and latest release of one binary
+    Info {
+        /// The name of the binary to describe
+        bin_name: String,
+    },
+    /// Shows where a binary is installed, whether bina manages it, and whether PATH finds another copy first
+    Which {
+        /// The name of the binary to locate
+        bin_name: String,
     },
     /// Downloads a specified binary using ubi
     Get {
-        /// The name of the binary to download
+        /// The name of the binary to download, optionally as name@tag
         bin_name: String,
+        /// Install the tag recorded in bina.lock and verify its checksum
+        #[arg(long, conflicts_with = "tag")]
+        locked: bool,
+        /// Release tag to install instead of the pinned or latest one
+        #[arg(long)]
+        tag: Option<String>,
+        /// Install from pre-downloaded assets instead of the network
+        #[arg(long, requires = "assets_dir")]
+        offline: bool,
+        /// Directory holding the release assets used by --offline
+        #[arg(long, value_name = "DIR", requires = "offline")]
+        assets_dir: Option<String>,
+    },
+    /// Runs a binary, installing it first if it's missing
+    Run {
+        /// The name of the binary to run
+        bin_name: String,
+        /// Arguments passed to the binary, after `--`
+        #[arg(last = true)]
+        args: Vec<String>,
     },
     /// Downloads all missing binaries
-    GetMissing,
+    GetMissing {
+        /// Install the tags recorded in bina.lock and verify their checksums
+        #[arg(long)]
+        locked: bool,
+        /// Maximum number of concurrent downloads
+        #[arg(short, long, default_value_t = DEFAULT_JOBS)]
+        jobs: usize,
+        /// Stop at the first binary that fails instead of carrying on with the rest
+        #[arg(long)]
+        fail_fast: bool,
+        #[command(flatten)]
+        filter: TagFilter,
+    },
+    /// Creates binaries.toml, optionally seeded from executables already installed
+    Init {
+        /// Add entries for recognized executables found in XDG_BIN_HOME
+        #[arg(long)]
+        scan: bool,
+        /// Overwrite an existing binaries.toml
+        #[arg(long)]
+        force: bool,
+    },
+    /// Prints every configured binary pinned to its installed version, for `import --from bina`
+    Export,
+    /// Adds the binaries listed in a manifest, mise, or asdf config to binaries.toml
+    Import {
+        /// Format of the file to import
+        #[arg(long, value_enum)]
+        from: ImportFormat,
+        /// Path of the manifest, mise.toml, or .tool-versions file
+        path: String,
+    },
+    /// Adds a binary from a GitHub repository to binaries.toml
+    Add {
+        /// The GitHub repository in owner/name form
+        repo: String,
+        /// Name of the entry, defaults to the repository name
+        #[arg(long)]
+        name: Option<String>,
+        /// Executable name, defaults to the entry name
+        #[arg(long)]
+        exe: Option<String>,
+        /// Argument that prints the version, prompted for when omitted
+        #[arg(long)]
+        version_arg: Option<String>,
+        /// Download the binary right after adding it
+        #[arg(long)]
+        install: bool,
+    },
+    /// Searches GitHub for repositories and offers to add one to binaries.toml
+    Search {
+        /// Search terms, e.g. the name of the tool
+        query: String,
+        /// Maximum number of repositories to list
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Removes an installed binary from XDG_BIN_HOME
+    Remove {
+        /// The name of the binary to remove
+        bin_name: String,
+        /// Also drop the entry from binaries.toml
+        #[arg(long)]
+        purge: bool,
+    },
+    /// Upgrades installed binaries that are behind their latest release
+    Update {
+        /// Don't show release notes for the new versions
+        #[arg(long)]
+        no_notes: bool,
+        /// Stop at the first binary that fails instead of carrying on with the rest
+        #[arg(long)]
+        fail_fast: bool,
+        #[command(flatten)]
+        filter: TagFilter,
+    },
+    /// Keeps checking for new releases, and installs them for binaries with `auto_update = true`
+    Watch {
+        /// Time between checks, e.g. 30m, 6h, or 1d
+        #[arg(long, default_value = "6h", value_parser = parse_age)]
+        interval: Duration,
+        /// Also announce new releases with a desktop notification
+        #[arg(long)]
+        notify: bool,
+        #[command(flatten)]
+        filter: TagFilter,
+    },
+    /// Switches a binary back to the version that was active before
+    Rollback {
+        /// The name of the binary to roll back
+        bin_name: String,
+        /// Stored tag to switch to instead of the previous one
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Browses binaries interactively and installs, updates, or removes the marked ones
+    Tui {
+        #[command(flatten)]
+        filter: TagFilter,
+    },
+    /// Lists every binary bina installed with its version, source, download URL, and checksum
+    Sbom,
+    /// Reports known vulnerabilities in the installed versions, from OSV.dev
+    Audit,
+    /// Lists executables in XDG_BIN_HOME that the config doesn't account for, and removes them with --yes
+    Prune {
+        /// Remove the listed binaries instead of only listing them
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Installs missing binaries and upgrades outdated ones to match the config
+    Sync {
+        /// Also remove binaries in XDG_BIN_HOME that are not in the config
+        #[arg(long)]
+        prune: bool,
+        /// Maximum number of concurrent downloads
+        #[arg(short, long, default_value_t = DEFAULT_JOBS)]
+        jobs: usize,
+        /// Stop at the first binary that fails instead of carrying on with the rest
+        #[arg(long)]
+        fail_fast: bool,
+        #[command(flatten)]
+        filter: TagFilter,
+    },
+    /// Replaces the running bina with its latest release
+    SelfUpdate,
+    /// Diagnoses the environment, config, GitHub access, and installed binaries
+    Doctor,
+    /// Inspects binaries.toml and the files it pulls in
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Manages downloaded release assets kept for reinstalls
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+    /// Runs release checks or updates on a schedule with a systemd user timer
+    Schedule {
+        #[command(subcommand)]
+        action: ScheduleCommand,
+    },
+    /// Prints a shell completion script
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+    /// Prints a roff man page generated from the command-line definitions
+    Man {
+        /// Subcommand to document instead of bina itself
+        subcommand: Option<String>,
+    },
     /// Links .config directory to ~/.local/dotfiles
     LinkConfig,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Binary {
-    name: String,
-    repo: String,
-    exe: String,
-    version_arg: String,
-}
+/// Longest description shown in the search table before it is cut off
+const DESCRIPTION_WIDTH: usize = 60;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Config {
-    binaries: Vec<Binary>,
+fn print_search_results(results: &[SearchResult], style: TableStyle) {
+    let mut table = style.table(&["#", "REPO", "STARS", "DESCRIPTION"], &[0, 2]);
+    for (i, result) in results.iter().enumerate() {
+        let description = result.description.as_deref().unwrap_or("");
+        let description = match description.char_indices().nth(DESCRIPTION_WIDTH) {
+            Some((end, _)) => format!("{}…", &description[..end]),
+            None => description.to_string(),
+        };
+        table.add_row(vec![
+            (i + 1).to_string(),
+            result.repo.clone(),
+            result.stars.to_string(),
+            description,
+        ]);
+    }
+    print_table(&table);
 }
 
-struct BinManager {
-    data: HashMap<String, [String; 3]>,
-    xdg_bin_home: String,
-    regex: Regex,
+/// Asks which search result to add, returning None when the answer is empty or out of range
+fn choose_search_result(results: &[SearchResult]) -> Result<Option<&SearchResult>, BinaError> {
+    let answer = prompt("Number of the repository to add", "none")?;
+    Ok(answer
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_sub(1))
+        .and_then(|index| results.get(index)))
 }
 
-fn new_bin_manager() -> Result<BinManager, Box<dyn std::error::Error>> {
-    let data = load_config()?;
-    let xdg_bin_home = env::var("XDG_BIN_HOME").map_err(|_| "XDG_BIN_HOME environment variable not set")?;
-    let regex = Regex::new(r"(\d+\.\d+\.\d+)").map_err(|_| "Invalid regex")?;
-    Ok(BinManager {
-        data,
-        xdg_bin_home,
-        regex,
-    })
+fn print_serialized<T: Serialize>(value: &T, format: OutputFormat) -> Result<(), BinaError> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+        OutputFormat::Table => unreachable!("tables are printed by the caller"),
+        OutputFormat::Cyclonedx => return Err("--format cyclonedx is only supported by sbom".into()),
+    }
+    Ok(())
 }
 
-fn load_config() -> Result<HashMap<String, [String; 3]>, Box<dyn std::error::Error>> {
-    let mut data = HashMap::new();
+const GREEN: &str = "32";
+const RED: &str = "31";
 
-    // Populate with internal default data
-    data.insert(
-        "nu".to_string(),
-        ["nushell/nushell".to_string(), "nu".to_string(), "--version".to_string()],
-    );
-    data.insert(
-        "uv".to_string(),
-        ["astral-sh/uv".to_string(), "uv".to_string(), "--version".to_string()],
-    );
-    data.insert(
-        "zoxide".to_string(),
-        [
-            "ajeetdsouza/zoxide".to_string(),
-            "zoxide".to_string(),
-            "--version".to_string(),
-        ],
-    );
-    data.insert(
-        "jj".to_string(),
-        ["jj-vcs/jj".to_string(), "jj".to_string(), "--version".to_string()],
-    );
-    data.insert(
-        "fzf".to_string(),
-        ["junegunn/fzf".to_string(), "fzf".to_string(), "--version".to_string()],
-    );
-    data.insert(
-        "gh".to_string(),
-        ["cli/cli".to_string(), "gh".to_string(), "--version".to_string()],
-    );
-    data.insert(
-        "yazi".to_string(),
-        ["sxyazi/yazi".to_string(), "yazi".to_string(), "--version".to_string()],
-    );
-    data.insert(
-        "micro".to_string(),
-        [
-            "zyedidia/micro".to_string(),
-            "micro".to_string(),
-            "--version".to_string(),
-        ],
-    );
-    data.insert(
-        "lazygit".to_string(),
-        [
-            "jesseduffield/lazygit".to_string(),
-            "lazygit".to_string(),
-            "--version".to_string(),
-        ],
-    );
-    data.insert(
-        "eza".to_string(),
-        [
-            "eza-community/eza".to_string(),
-            "eza".to_string(),
-            "--version".to_string(),
-        ],
-    );
-    data.insert(
-        "bat".to_string(),
-        ["sharkdp/bat".to_string(), "bat".to_string(), "--version".to_string()],
-    );
-    data.insert(
-        "rclone".to_string(),
-        [
-            "rclone/rclone".to_string(),
-            "rclone".to_string(),
-            "--version".to_string(),
-        ],
-    );
-    data.insert(
-        "gix".to_string(),
-        [
-            "GitoxideLabs/gitoxide".to_string(),
-            "gix".to_string(),
-            "--version".to_string(),
-        ],
-    );
-    data.insert(
-        "kopia".to_string(),
-        ["kopia/kopia".to_string(), "kopia".to_string(), "--version".to_string()],
-    );
+fn use_color(choice: ColorChoice) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && io::stdout().is_terminal(),
+    }
+}
 
-    // Attempt to load from TOML file and add/override
-    let config_dir = env::var("XDG_CONFIG_HOME").unwrap_or_else(|_| {
-        let home = env::var("HOME").expect("HOME environment variable not set");
-        format!("{}/.config", home)
-    });
-    let toml_path = format!("{}/bina/binaries.toml", config_dir);
+/// Left-aligns text to a column width, then wraps it in an ANSI color so padding stays correct
+fn paint(text: &str, width: usize, color: Option<&str>) -> String {
+    let padded = format!("{:<width$}", text, width = width);
+    match color {
+        Some(code) => format!("\x1b[{}m{}\x1b[0m", code, padded),
+        None => padded,
+    }
+}
+
+/// Spaces between table columns
+const COLUMN_GAP: usize = 2;
 
-    if let Ok(toml_str) = fs::read_to_string(&toml_path) {
-        let config: Config =
-            toml::from_str(&toml_str).map_err(|_| format!("Failed to parse binaries.toml from {}", toml_path))?;
+/// How tables are drawn, from --borders and --color
+#[derive(Clone, Copy)]
+struct TableStyle {
+    borders: bool,
+    color: bool,
+}
 
-        for binary in config.binaries {
-            data.insert(binary.name.clone(), [binary.repo, binary.exe, binary.version_arg]);
+impl TableStyle {
+    fn new(cli: &Cli) -> TableStyle {
+        TableStyle {
+            borders: cli.borders,
+            color: use_color(cli.color),
         }
-    } // If file doesn't exist or can't be read/parsed, silently use internal data only
+    }
 
-    Ok(data)
+    /// Empty table with these headers, right-aligning the columns at `right_aligned`
+    fn table(&self, headers: &[&str], right_aligned: &[usize]) -> comfy_table::Table {
+        let mut table = comfy_table::Table::new();
+        if self.borders {
+            table.load_preset(presets::UTF8_FULL_CONDENSED);
+        } else {
+            // Plain columns as before, without the blank line a header separator of spaces would leave
+            table.load_preset(presets::NOTHING);
+            table.remove_style(TableComponent::HeaderLines);
+        }
+        if self.color {
+            table.enforce_styling();
+        } else {
+            table.force_no_tty();
+        }
+        table.set_header(headers.to_vec());
+        for (index, column) in table.column_iter_mut().enumerate() {
+            if !self.borders {
+                column.set_padding((0, COLUMN_GAP as u16));
+            }
+            if right_aligned.contains(&index) {
+                column.set_cell_alignment(CellAlignment::Right);
+            }
+        }
+        table
+    }
 }
 
-fn ensure_bin_directory(xdg_bin_home: &str) -> Result<(), Box<dyn std::error::Error>> {
-    if xdg_bin_home.is_empty() {
-        return Err("XDG_BIN_HOME environment variable is not set".into());
+/// Prints a table, dropping the padding borderless tables leave at the end of each line
+fn print_table(table: &comfy_table::Table) {
+    for line in table.lines() {
+        println!("{}", line.trim_end());
     }
+}
 
-    let path = Path::new(xdg_bin_home);
-    if !path.exists() {
-        println!("Creating directory {}...", xdg_bin_home);
-        fs::create_dir_all(path)?;
+/// Sorts check results by `key`, then by name
+fn sort_results(results: &mut [BinaryStatus], key: SortKey, reverse: bool) {
+    results.sort_by(|a, b| a.binary.cmp(&b.binary));
+    match key {
+        SortKey::Name => {}
+        SortKey::Status => {
+            results.sort_by_key(|result| (result.installed, !result.not_executable, result.managed));
+        }
+        SortKey::Version => results.sort_by_cached_key(|result| result.version.as_deref().map(version_sort_key)),
+        SortKey::Outdated => results.sort_by_key(|result| !result.is_outdated()),
+    }
+    if reverse {
+        results.reverse();
     }
-    Ok(())
 }
 
-fn link_config_directory() -> Result<(), Box<dyn std::error::Error>> {
-    let home = env::var("HOME").map_err(|_| "HOME environment variable not set")?;
-    let config_dir = env::var("XDG_CONFIG_HOME").unwrap_or_else(|_| format!("{}/.config", home));
-    let dotfiles_dir = format!("{}/.local/dotfiles", home);
+/// Sort key for a version, since sorting needs a total order and `Version` only has a partial one
+///
+/// Numeric versions come first, then dates, then anything else by its text.
+fn version_sort_key(version: &str) -> (u8, Vec<u64>, bool, String) {
+    match Version::parse(version) {
+        Version::Semver { mut parts, pre } => {
+            // 1.2 and 1.2.0 are the same version
+            while parts.last() == Some(&0) {
+                parts.pop();
+            }
+            (0, parts, pre.is_none(), pre.unwrap_or_default())
+        }
+        Version::Calver(parts) => (1, parts, true, String::new()),
+        Version::Opaque(text) => (2, vec![], true, text),
+    }
+}
 
-    // Ensure the dotfiles directory exists
-    let dotfiles_path = Path::new(&dotfiles_dir);
-    if !dotfiles_path.exists() {
-        println!("Creating dotfiles directory {}...", dotfiles_dir);
-        fs::create_dir_all(dotfiles_path)?;
+fn print_results(results: Vec<BinaryStatus>, check_latest: bool, style: TableStyle) {
+    let mut headers = vec!["BINARY", "STATUS", "VERSION"];
+    if check_latest {
+        headers.push("LATEST");
     }
+    let mut table = style.table(&headers, &[2, 3]);
 
-    // Check if .config exists
-    let config_path = Path::new(&config_dir);
-    if !config_path.exists() {
-        println!("Creating .config directory {}...", config_dir);
-        fs::create_dir_all(config_path)?;
+    // An arrow marks versions behind the latest release, which is readable without color too
+    let latest_cells: Vec<String> = results
+        .iter()
+        .map(|result| {
+            if result.is_outdated() {
+                format!("↑ {}", result.latest_label())
+            } else {
+                result.latest_label().to_string()
+            }
+        })
+        .collect();
+    for (result, latest_cell) in results.iter().zip(latest_cells) {
+        let (status, status_color) = match (result.installed, result.managed) {
+            (false, _) => ("✗", comfy_table::Color::Red),
+            _ if result.not_executable => ("✗ not executable", comfy_table::Color::Red),
+            (true, true) => ("✓", comfy_table::Color::Green),
+            (true, false) => ("✓ unmanaged", comfy_table::Color::Yellow),
+        };
+        let mut version = comfy_table::Cell::new(result.version.as_deref().unwrap_or("-"));
+        if result.is_outdated() {
+            version = version.fg(comfy_table::Color::Yellow);
+        }
+        let mut row = vec![
+            comfy_table::Cell::new(&result.binary),
+            comfy_table::Cell::new(status).fg(status_color),
+            version,
+        ];
+        if check_latest {
+            row.push(comfy_table::Cell::new(latest_cell));
+        }
+        table.add_row(row);
     }
+    print_table(&table);
+}
 
-    // Remove existing .config if it's a symlink
-    if config_path.is_symlink() {
-        println!("Removing existing .config symlink...");
-        fs::remove_file(&config_dir)?;
+fn print_info(info: &BinaryInfo) {
+    let fields = [
+        ("Name", Some(info.name.as_str())),
+        ("Repository", Some(info.repo.as_str())),
+        ("Executable", Some(info.exe.as_str())),
+        ("Install path", Some(info.install_path.as_str())),
+        ("Installed", info.installed_version.as_deref()),
+        ("Latest", info.latest_tag.as_deref()),
+        ("Released", info.release_date.as_deref()),
+        ("Asset", info.asset.as_deref()),
+        ("Release URL", info.release_url.as_deref()),
+    ];
+    let width = fields.iter().map(|(label, _)| label.len()).max().unwrap_or(0) + COLUMN_GAP;
+    for (label, value) in fields {
+        println!("{}{}", paint(label, width, None), value.unwrap_or("-"));
     }
+}
 
-    // Create symbolic link
-    println!("Creating symbolic link from {} to {}...", config_dir, dotfiles_dir);
-    #[cfg(unix)]
-    std::os::unix::fs::symlink(&dotfiles_dir, &config_dir)?;
-    #[cfg(windows)]
-    std::os::windows::fs::symlink_dir(&dotfiles_dir, &config_dir)?;
+fn print_which(which: &WhichInfo) {
+    let installed = match (which.installed, which.managed) {
+        (false, _) => "no",
+        (true, true) => "yes, by bina",
+        (true, false) => "yes, not by bina",
+    };
+    let fields = [
+        ("Name", Some(which.name.as_str())),
+        ("Path", Some(which.path.as_str())),
+        ("Installed", Some(installed)),
+        ("Tag", which.tag.as_deref()),
+        ("Version", which.version.as_deref()),
+        ("Shadowed by", which.shadowed_by.as_deref()),
+    ];
+    let width = fields.iter().map(|(label, _)| label.len()).max().unwrap_or(0) + COLUMN_GAP;
+    for (label, value) in fields {
+        println!("{}{}", paint(label, width, None), value.unwrap_or("-"));
+    }
+}
 
-    println!("Successfully linked .config to ~/.local/dotfiles");
-    Ok(())
+fn print_vulnerabilities(vulnerabilities: &[Vulnerability], style: TableStyle) {
+    let mut table = style.table(&["BINARY", "VERSION", "ID", "FIXED IN", "SUMMARY"], &[1, 3]);
+    for vulnerability in vulnerabilities {
+        table.add_row(vec![
+            vulnerability.binary.as_str(),
+            &vulnerability.version,
+            &vulnerability.id,
+            vulnerability.fixed.as_deref().unwrap_or("-"),
+            vulnerability.summary.as_deref().unwrap_or(""),
+        ]);
+    }
+    print_table(&table);
 }
 
-async fn check_latest_release(repo: &str) -> String {
-    let client = Client::new();
-    let url = format!("https://api.github.com/repos/{}/releases/latest", repo);
-    let response = client
-        .get(&url)
-        .header("Accept", "application/vnd.github.v3+json")
-        .header("User-Agent", "reqwest")
-        .send()
-        .await;
-
-    match response {
-        Ok(response) if response.status().is_success() => match response.json::<Value>().await {
-            Ok(json) => json["tag_name"]
-                .as_str()
-                .map(String::from)
-                .unwrap_or("Error".to_string()),
-            Err(_) => "Error".to_string(),
-        },
-        _ => "Error".to_string(),
+fn print_sbom(entries: &[SbomEntry], style: TableStyle) {
+    let mut table = style.table(&["BINARY", "VERSION", "REPO", "SHA256", "DOWNLOAD URL"], &[1]);
+    for entry in entries {
+        table.add_row(vec![
+            entry.name.as_str(),
+            &entry.version,
+            &entry.repo,
+            if entry.sha256.is_empty() { "-" } else { &entry.sha256 },
+            entry.download_url.as_deref().unwrap_or("-"),
+        ]);
     }
+    print_table(&table);
 }
 
-async fn get_binary(bin_name: &str, manager: &BinManager) -> Result<(), Box<dyn std::error::Error>> {
-    ensure_bin_directory(&manager.xdg_bin_home)?;
-    let bin_data = manager
-        .data
-        .get(bin_name)
-        .ok_or_else(|| format!("Binary '{}' not found in data", bin_name))?;
+fn print_config(manager: &BinManager, style: TableStyle) {
+    let mut table = style.table(&["BINARY", "REPO", "EXE", "VERSION ARG"], &[]);
+    for binary in manager.data.values().filter(|binary| manager.selects(binary)) {
+        table.add_row(vec![
+            binary.name.clone(),
+            binary.repo.clone(),
+            binary.exe.join(", "),
+            binary.version_arg.clone(),
+        ]);
+    }
+    print_table(&table);
+}
 
-    let mut ubi = UbiBuilder::new()
-        .project(&bin_data[0])
-        .install_dir(&manager.xdg_bin_home)
-        .exe(&bin_data[1])
-        .build()?;
-    ubi.install_binary().await?;
-    println!("Successfully downloaded {}", bin_name);
-    Ok(())
+/// One binary in the interactive view
+struct TuiRow<'a> {
+    binary: &'a Binary,
+    installed: Option<String>,
+    latest: Option<String>,
+    fetching: bool,
+    marked: bool,
 }
 
-async fn check_binaries(manager: &BinManager, check_latest: bool) -> Vec<HashMap<String, String>> {
-    if ensure_bin_directory(&manager.xdg_bin_home).is_err() {
-        return vec![];
+impl TuiRow<'_> {
+    fn status(&self) -> (&'static str, Color) {
+        match (&self.installed, &self.latest) {
+            (None, _) => ("missing", Color::Red),
+            (Some(_), None) if self.fetching => ("checking", Color::DarkGray),
+            (Some(_), None) => ("unknown", Color::Yellow),
+            (Some(installed), Some(latest)) if !Version::parse(installed).is_outdated_by(&Version::parse(latest)) => {
+                ("up to date", Color::Green)
+            }
+            (Some(_), Some(_)) => ("outdated", Color::Yellow),
+        }
     }
+}
+
+/// What a key press asks to do with the marked rows
+#[derive(Clone, Copy)]
+enum TuiAction {
+    Install,
+    Update,
+    Remove,
+}
+
+const TUI_HELP: &str = "↑/↓ move  space mark  i install  u update  d remove  r refresh  q quit";
+
+fn installed_tag(binary: &Binary, manager: &BinManager) -> Option<String> {
+    exe_path(binary, manager)
+        .exists()
+        .then(|| current_version(binary, manager).unwrap_or_else(|| "?".to_string()))
+}
+
+/// Target version of a row: the pinned version, or the latest release
+async fn fetch_row_latest(
+    client: &Client,
+    binary: &Binary,
+    manager: &BinManager,
+    index: usize,
+) -> (usize, Option<String>) {
+    let latest = match &binary.version {
+        Some(version) => Some(parse_version(version, binary, manager).unwrap_or(version.clone())),
+        None => latest_version(client, binary, manager).await,
+    };
+    (index, latest)
+}
+
+async fn run_tui(manager: &BinManager) -> Result<(), BinaError> {
+    let binaries: Vec<&Binary> = manager.data.values().filter(|binary| manager.selects(binary)).collect();
+    let mut rows: Vec<TuiRow> = binaries
+        .into_iter()
+        .map(|binary| TuiRow {
+            binary,
+            installed: installed_tag(binary, manager),
+            latest: None,
+            fetching: true,
+            marked: false,
+        })
+        .collect();
+
+    let mut terminal = ratatui::init();
+    let result = tui_loop(&mut terminal, &mut rows, manager).await;
+    ratatui::restore();
+    result
+}
 
-    let binaries: Vec<String> = fs::read_dir(&manager.xdg_bin_home)
-        .expect("Failed to read XDG_BIN_HOME")
-        .filter_map(|entry: Result<DirEntry, _>| entry.ok().and_then(|e| e.file_name().into_string().ok()))
+async fn tui_loop(
+    terminal: &mut DefaultTerminal,
+    rows: &mut [TuiRow<'_>],
+    manager: &BinManager,
+) -> Result<(), BinaError> {
+    let client = &manager.client;
+    let mut fetches: FuturesUnordered<_> = rows
+        .iter()
+        .enumerate()
+        .map(|(index, row)| fetch_row_latest(client, row.binary, manager, index))
         .collect();
+    let mut state = TableState::default().with_selected((!rows.is_empty()).then_some(0));
+    let mut message = String::new();
 
-    let mut results = vec![];
-    for (bin_name, bin_data) in &manager.data {
-        let mut result = HashMap::new();
-        result.insert("Binary".to_string(), bin_name.to_string());
-
-        if binaries.contains(bin_name) {
-            let version = Command::new(bin_name)
-                .arg(&bin_data[2])
-                .output()
-                .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
-                .map(|version_output| {
-                    manager
-                        .regex
-                        .captures(&version_output)
-                        .and_then(|cap| cap.get(1).map(|m| m.as_str().to_string()))
-                        .unwrap_or("-".to_string())
-                })
-                .unwrap_or("-".to_string());
-            result.insert("Status".to_string(), "✓".to_string());
-            result.insert("Version".to_string(), version);
-            if check_latest {
-                let latest = check_latest_release(&bin_data[0]).await;
-                let latest_version = manager
-                    .regex
-                    .captures(&latest)
-                    .and_then(|cap| cap.get(1).map(|m| m.as_str().to_string()))
-                    .unwrap_or("-".to_string());
-                result.insert("Latest".to_string(), latest_version);
+    loop {
+        terminal.draw(|frame| draw_tui(frame, rows, &mut state, &message))?;
+        // Redraw as soon as a release lookup lands, otherwise check for key presses
+        tokio::select! {
+            Some((index, latest)) = fetches.next(), if !fetches.is_empty() => {
+                rows[index].latest = latest;
+                rows[index].fetching = false;
+                continue;
             }
-        } else {
-            result.insert("Status".to_string(), "✗".to_string());
-            result.insert("Version".to_string(), "-".to_string());
-            if check_latest {
-                let latest = check_latest_release(&bin_data[0]).await;
-                let latest_version = manager
-                    .regex
-                    .captures(&latest)
-                    .and_then(|cap| cap.get(1).map(|m| m.as_str().to_string()))
-                    .unwrap_or("-".to_string());
-                result.insert("Latest".to_string(), latest_version);
+            _ = tokio::time::sleep(Duration::from_millis(50)) => {}
+        }
+        if !event::poll(Duration::ZERO)? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let action = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Down | KeyCode::Char('j') => {
+                state.select_next();
+                continue;
             }
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.select_previous();
+                continue;
+            }
+            KeyCode::Char(' ') => {
+                if let Some(row) = state.selected().and_then(|index| rows.get_mut(index)) {
+                    row.marked = !row.marked;
+                }
+                continue;
+            }
+            KeyCode::Char('r') => {
+                for (index, row) in rows.iter_mut().enumerate() {
+                    row.installed = installed_tag(row.binary, manager);
+                    row.latest = None;
+                    row.fetching = true;
+                    fetches.push(fetch_row_latest(client, row.binary, manager, index));
+                }
+                message.clear();
+                continue;
+            }
+            KeyCode::Char('i') => TuiAction::Install,
+            KeyCode::Char('u') => TuiAction::Update,
+            KeyCode::Char('d') => TuiAction::Remove,
+            _ => continue,
+        };
+
+        // Act on the marked rows, or the highlighted one when nothing is marked
+        let mut targets: Vec<usize> = (0..rows.len()).filter(|&index| rows[index].marked).collect();
+        if targets.is_empty() {
+            targets.extend(state.selected().filter(|&index| index < rows.len()));
         }
-        results.push(result);
+        targets.retain(|&index| match action {
+            TuiAction::Install => rows[index].installed.is_none(),
+            TuiAction::Update | TuiAction::Remove => rows[index].installed.is_some(),
+        });
+        if targets.is_empty() {
+            message = "Nothing to do for the selected binaries".to_string();
+            continue;
+        }
+
+        // Leave the alternate screen so download progress and prompts show normally
+        ratatui::restore();
+        let mut outcomes = vec![];
+        for &index in &targets {
+            let name = &rows[index].binary.name;
+            let result = match action {
+                TuiAction::Install | TuiAction::Update => get_binary(name, manager, false, None).await.map(|_| ()),
+                TuiAction::Remove => remove_binary(name, false, manager),
+            };
+            outcomes.push(match result {
+                Ok(()) => format!("{} done", name),
+                Err(e) => format!("{} failed: {}", name, e),
+            });
+            rows[index].installed = installed_tag(rows[index].binary, manager);
+            rows[index].marked = false;
+        }
+        *terminal = ratatui::init();
+        message = outcomes.join(", ");
     }
-    results
 }
 
-async fn get_missing_binaries(manager: &BinManager) -> Result<String, Box<dyn std::error::Error>> {
-    ensure_bin_directory(&manager.xdg_bin_home)?;
-    let binaries: Vec<String> = fs::read_dir(&manager.xdg_bin_home)
-        .expect("Failed to read XDG_BIN_HOME")
-        .filter_map(|entry: Result<DirEntry, _>| entry.ok().and_then(|e| e.file_name().into_string().ok()))
-        .collect();
+fn draw_tui(frame: &mut Frame, rows: &[TuiRow], state: &mut TableState, message: &str) {
+    let [table_area, footer_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let header = Row::new(["", "BINARY", "REPO", "INSTALLED", "LATEST", "STATUS"]).bold();
+    let table_rows = rows.iter().map(|row| {
+        let (status, color) = row.status();
+        Row::new([
+            Cell::from(if row.marked { "*" } else { " " }),
+            Cell::from(row.binary.name.as_str()),
+            Cell::from(row.binary.repo.as_str()),
+            Cell::from(row.installed.as_deref().unwrap_or("-")),
+            Cell::from(row.latest.as_deref().unwrap_or(if row.fetching { "…" } else { "-" })),
+            Cell::from(status).style(Style::new().fg(color)),
+        ])
+    });
+    let widths = [
+        Constraint::Length(1),
+        Constraint::Fill(1),
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+        Constraint::Length(10),
+    ];
+    let table = Table::new(table_rows, widths)
+        .header(header)
+        .block(Block::bordered().title(" bina "))
+        .row_highlight_style(Style::new().reversed());
+    frame.render_stateful_widget(table, table_area, state);
+    let footer = if message.is_empty() { TUI_HELP } else { message };
+    frame.render_widget(Paragraph::new(footer), footer_area);
+}
 
-    let not_found: Vec<String> = manager
-        .data
-        .keys()
-        .filter(|bin_name| !binaries.contains(bin_name))
-        .cloned()
-        .collect();
+fn init_logging(verbose: u8, quiet: u8) {
+    let level = match (verbose, quiet) {
+        (0, 0) => LevelFilter::INFO,
+        (1, _) => LevelFilter::DEBUG,
+        (_, 0) => LevelFilter::TRACE,
+        (_, 1) => LevelFilter::WARN,
+        (_, _) => LevelFilter::ERROR,
+    };
+    // Dependencies only log their warnings unless bina or ubi output is requested
+    let targets = Targets::new()
+        .with_target("bina", level)
+        .with_target("ubi", level)
+        .with_default(LevelFilter::WARN.min(level));
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(io::stderr)
+                .without_time()
+                .with_target(false)
+                .with_level(verbose > 0),
+        )
+        .with(targets)
+        .init();
+}
 
-    if not_found.is_empty() {
-        return Ok("All binaries are already present.".to_string());
+fn print_completions(shell: Shell) -> Result<(), BinaError> {
+    // Bake the configured names into the script so `get` and `remove` complete them
+    let mut names: Vec<String> = load_config()?.binaries.into_iter().map(|binary| binary.name).collect();
+    names.sort();
+    let mut command = Cli::command();
+    for subcommand in ["get", "remove"] {
+        let names = names.clone();
+        command = command.mut_subcommand(subcommand, |sub| {
+            sub.mut_arg("bin_name", |arg| arg.value_parser(PossibleValuesParser::new(names)))
+        });
+    }
+    clap_complete::generate(shell, &mut command, "bina", &mut io::stdout());
+    Ok(())
+}
+
+fn print_man_page(subcommand: Option<&str>) -> Result<(), BinaError> {
+    let command = Cli::command();
+    let command = match subcommand {
+        Some(name) => {
+            let page_name = format!("bina-{}", name);
+            command
+                .find_subcommand(name)
+                .ok_or_else(|| format!("Unknown subcommand '{}'", name))?
+                .clone()
+                .name(page_name)
+        }
+        None => command,
+    };
+    clap_mangen::Man::new(command).render(&mut io::stdout())?;
+    Ok(())
+}
+
+async fn diagnose(cli: &Cli) -> Vec<Diagnosis> {
+    let mut diagnoses = vec![];
+
+    match bin_home() {
+        Err(e) => diagnoses.push(Diagnosis::problem(
+            e.to_string(),
+            "export XDG_BIN_HOME=\"$HOME/.local/bin\" in your shell profile",
+        )),
+        Ok(dir) => {
+            let probe = Path::new(&dir).join(".bina-doctor");
+            if !Path::new(&dir).is_dir() {
+                diagnoses.push(Diagnosis::problem(
+                    format!("{} does not exist", dir),
+                    format!("mkdir -p {}", dir),
+                ));
+            } else if fs::write(&probe, b"").and_then(|_| fs::remove_file(&probe)).is_err() {
+                diagnoses.push(Diagnosis::problem(
+                    format!("{} is not writable", dir),
+                    format!("fix the permissions of {}", dir),
+                ));
+            } else {
+                diagnoses.push(Diagnosis::ok(format!("{} is writable", dir)));
+            }
+            let on_path =
+                env::var_os("PATH").is_some_and(|path| env::split_paths(&path).any(|entry| entry == Path::new(&dir)));
+            if on_path {
+                diagnoses.push(Diagnosis::ok(format!("{} is on PATH", dir)));
+            } else {
+                diagnoses.push(Diagnosis::problem(
+                    format!("{} is not on PATH", dir),
+                    format!("export PATH=\"{}:$PATH\" in your shell profile", dir),
+                ));
+            }
+        }
     }
 
-    for bin_name in not_found {
-        println!("Downloading {}...", bin_name);
-        get_binary(&bin_name, manager).await?;
+    let toml_path = config_path();
+    match read_config_file(&toml_path) {
+        Ok(config) => diagnoses.push(Diagnosis::ok(format!(
+            "{} parses ({} entries)",
+            toml_path,
+            config.binaries.len()
+        ))),
+        Err(e) => {
+            diagnoses.push(Diagnosis::problem(
+                e.to_string(),
+                format!("fix the TOML in {}", toml_path),
+            ));
+            return diagnoses;
+        }
     }
-    Ok("".to_string())
+
+    let manager = match new_bin_manager(cli.target_os.clone(), cli.target_arch.clone()) {
+        Ok(manager) => manager,
+        Err(e) => {
+            diagnoses.push(Diagnosis::problem(
+                e.to_string(),
+                "fix the error above and rerun bina doctor",
+            ));
+            return diagnoses;
+        }
+    };
+
+    let mut request = manager
+        .client
+        .get(format!("{}/rate_limit", manager.github_api_base_url));
+    if let Some(token) = &manager.github_token {
+        request = request.bearer_auth(token);
+    }
+    match send_with_retries(request, &manager).await {
+        Err(e) => diagnoses.push(Diagnosis::problem(
+            format!("GitHub is unreachable: {}", e),
+            "check your network connection and proxy settings",
+        )),
+        Ok(response) if response.status() == reqwest::StatusCode::UNAUTHORIZED => diagnoses.push(Diagnosis::problem(
+            "GitHub rejected the configured token",
+            "renew GITHUB_TOKEN, GH_TOKEN, or github_token in binaries.toml",
+        )),
+        Ok(response) => {
+            let json: Value = response.json().await.unwrap_or_default();
+            let remaining = json["rate"]["remaining"].as_u64().unwrap_or(0);
+            let limit = json["rate"]["limit"].as_u64().unwrap_or(0);
+            let auth = if manager.github_token.is_some() {
+                "authenticated"
+            } else {
+                "unauthenticated"
+            };
+            if remaining == 0 {
+                diagnoses.push(Diagnosis::problem(
+                    format!("GitHub rate limit exhausted ({}, limit {})", auth, limit),
+                    "set GITHUB_TOKEN or wait for the limit to reset",
+                ));
+            } else {
+                diagnoses.push(Diagnosis::ok(format!(
+                    "GitHub is reachable ({}, {}/{} requests left)",
+                    auth, remaining, limit
+                )));
+            }
+        }
+    }
+
+    let binaries: Vec<&Binary> = manager.data.values().collect();
+    for binary in binaries {
+        let exe_path = exe_path(binary, &manager);
+        if fs::symlink_metadata(&exe_path).is_ok() && !exe_path.exists() {
+            diagnoses.push(Diagnosis::problem(
+                format!("{} is a dangling link", exe_path.display()),
+                format!("bina get {}", binary.name),
+            ));
+            continue;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Ok(metadata) = fs::metadata(&exe_path)
+                && metadata.permissions().mode() & 0o111 == 0
+            {
+                diagnoses.push(Diagnosis::problem(
+                    format!("{} is not executable", exe_path.display()),
+                    format!("chmod +x {}", exe_path.display()),
+                ));
+            }
+        }
+    }
+    diagnoses
 }
 
-fn print_results(results: Vec<HashMap<String, String>>, check_latest: bool) {
-    const WIDTHS: [usize; 4] = [15, 10, 15, 15];
+/// Prints what a batch command did, failing when it stopped early or any binary failed
+fn finish_batch(summary: &BatchSummary, result: Result<(), BinaError>) -> Result<(), BinaError> {
+    println!("{}", summary.report());
+    result?;
+    if !summary.failed.is_empty() {
+        return Err(format!("{} binaries failed", summary.failed.len()).into());
+    }
+    Ok(())
+}
 
-    if check_latest {
-        println!(
-            "{:<width1$}{:<width2$}{:<width3$}{:<width4$}",
-            "BINARY",
-            "STATUS",
-            "VERSION",
-            "LATEST",
-            width1 = WIDTHS[0],
-            width2 = WIDTHS[1],
-            width3 = WIDTHS[2],
-            width4 = WIDTHS[3]
-        );
-    } else {
+fn print_diagnoses(diagnoses: &[Diagnosis], color: bool) -> Result<(), BinaError> {
+    for diagnosis in diagnoses {
+        let (mark, mark_color) = match diagnosis.fix {
+            None => ("✓", GREEN),
+            Some(_) => ("✗", RED),
+        };
         println!(
-            "{:<width1$}{:<width2$}{:<width3$}",
-            "BINARY",
-            "STATUS",
-            "VERSION",
-            width1 = WIDTHS[0],
-            width2 = WIDTHS[1],
-            width3 = WIDTHS[2]
+            "{} {}",
+            paint(mark, 1, Some(mark_color).filter(|_| color)),
+            diagnosis.message
         );
+        if let Some(fix) = &diagnosis.fix {
+            println!("  fix: {}", fix);
+        }
+    }
+    let problems = diagnoses.iter().filter(|diagnosis| diagnosis.fix.is_some()).count();
+    if problems > 0 {
+        return Err(format!("{} problems found", problems).into());
     }
+    Ok(())
+}
 
-    for result in results {
-        if check_latest {
-            println!(
-                "{:<width1$}{:<width2$}{:<width3$}{:<width4$}",
-                result["Binary"],
-                result["Status"],
-                result["Version"],
-                result.get("Latest").unwrap_or(&"-".to_string()),
-                width1 = WIDTHS[0],
-                width2 = WIDTHS[1],
-                width3 = WIDTHS[2],
-                width4 = WIDTHS[3]
-            );
-        } else {
-            println!(
-                "{:<width1$}{:<width2$}{:<width3$}",
-                result["Binary"],
-                result["Status"],
-                result["Version"],
-                width1 = WIDTHS[0],
-                width2 = WIDTHS[1],
-                width3 = WIDTHS[2]
-            );
-        }
+/// Opens binaries.toml in the user's editor, creating its directory first
+fn edit_config() -> Result<(), BinaError> {
+    let toml_path = local_config_path()?;
+    if let Some(parent) = Path::new(&toml_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let default_editor = if cfg!(windows) { "notepad" } else { "vi" };
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| default_editor.to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().ok_or("VISUAL or EDITOR is empty")?;
+    let status = Command::new(program)
+        .args(words)
+        .arg(&toml_path)
+        .status()
+        .map_err(|e| format!("Failed to start editor {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("Editor {} exited with {}", program, status).into());
     }
+    Ok(())
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
-    let manager = new_bin_manager()?;
+async fn main() -> ExitCode {
+    let mut cli = Cli::parse();
+    // The most common question, what's installed, needs no typing; help stays behind -h
+    if cli.command.is_none() {
+        cli.command = Cli::parse_from(["bina", "check"]).command;
+    }
+    init_logging(cli.verbose, cli.quiet);
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
 
+async fn run(cli: Cli) -> Result<(), BinaError> {
+    match &cli.config {
+        Some(config) if is_remote_config(config) => use_remote_config(config, cli.config_sha256.as_deref()).await?,
+        Some(config) => set_config_path(config.clone()),
+        None => {}
+    }
+    match &cli.command {
+        Some(Commands::Completions { shell }) => return print_completions(*shell),
+        Some(Commands::Man { subcommand }) => return print_man_page(subcommand.as_deref()),
+        Some(Commands::Export) => {
+            let manifest = export_manifest()?;
+            match cli.format {
+                OutputFormat::Table => print!("{}", toml::to_string(&manifest)?),
+                format => print_serialized(&manifest, format)?,
+            }
+            return Ok(());
+        }
+        Some(Commands::Import { from, path }) => {
+            println!("{}", import_binaries(*from, path, cli.dry_run)?);
+            return Ok(());
+        }
+        Some(Commands::Init { scan, force }) => {
+            println!("{}", init_config(*scan, *force, cli.dry_run)?);
+            return Ok(());
+        }
+        // Doctor loads the config itself so it can report why loading fails
+        Some(Commands::Doctor) => return print_diagnoses(&diagnose(&cli).await, use_color(cli.color)),
+        Some(Commands::Config {
+            action: ConfigCommand::Validate,
+        }) => return print_diagnoses(&validate_config(), use_color(cli.color)),
+        Some(Commands::Config {
+            action: ConfigCommand::Edit,
+        }) => {
+            edit_config()?;
+            return print_diagnoses(&validate_config(), use_color(cli.color));
+        }
+        Some(Commands::Cache {
+            action: CacheCommand::Clean { older_than },
+        }) => {
+            println!("{}", clean_cache(*older_than, cli.dry_run)?);
+            return Ok(());
+        }
+        Some(Commands::Cache {
+            action: CacheCommand::Size,
+        }) => {
+            println!("{}", cache_size());
+            return Ok(());
+        }
+        Some(Commands::Schedule {
+            action: ScheduleCommand::Install { interval, update },
+        }) => {
+            println!("{}", install_schedule(interval, *update, cli.dry_run)?);
+            return Ok(());
+        }
+        Some(Commands::Schedule {
+            action: ScheduleCommand::Remove,
+        }) => {
+            println!("{}", remove_schedule(cli.dry_run)?);
+            return Ok(());
+        }
+        _ => {}
+    }
+    let mut manager = new_bin_manager(cli.target_os.clone(), cli.target_arch.clone())?;
+    manager.dry_run = cli.dry_run;
+    if let Some(rate) = cli.limit_rate {
+        manager.rate_limit = Some(RateLimit::new(rate));
+    }
+    manager.tags = match &cli.command {
+        Some(
+            Commands::Check { filter, .. }
+            | Commands::List { filter }
+            | Commands::GetMissing { filter, .. }
+            | Commands::Update { filter, .. }
+            | Commands::Watch { filter, .. }
+            | Commands::Sync { filter, .. }
+            | Commands::Tui { filter },
+        ) => filter.tags.clone(),
+        _ => vec![],
+    };
+    manager.fail_fast = matches!(
+        &cli.command,
+        Some(
+            Commands::GetMissing { fail_fast: true, .. }
+                | Commands::Update { fail_fast: true, .. }
+                | Commands::Sync { fail_fast: true, .. }
+        )
+    );
+
+    let table_style = TableStyle::new(&cli);
     match cli.command {
-        Some(Commands::Check { latest }) => {
-            let results = check_binaries(&manager, latest).await;
-            print_results(results, latest);
+        Some(Commands::Check {
+            latest,
+            notify,
+            fail_if_missing,
+            fail_if_outdated,
+            outdated_only,
+            sort,
+            reverse,
+            names,
+            ..
+        }) => {
+            let latest = latest || fail_if_outdated || outdated_only;
+            if notify && !latest {
+                return Err("--notify needs --latest, --outdated-only, or --fail-if-outdated".into());
+            }
+            let patterns = names
+                .iter()
+                .map(|name| Pattern::new(name).map_err(|e| format!("Invalid pattern '{}': {}", name, e)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut results = check_binaries(&manager, latest, &patterns).await;
+            if notify {
+                let outdated: Vec<String> = results
+                    .iter()
+                    .filter(|status| status.is_outdated())
+                    .map(|status| {
+                        format!(
+                            "{} {} -> {}",
+                            status.binary,
+                            status.version.as_deref().unwrap_or("-"),
+                            status.latest_label()
+                        )
+                    })
+                    .collect();
+                notify_updates(&outdated);
+            }
+            if results.is_empty() && !patterns.is_empty() {
+                warn!("No configured binaries match {}", names.join(" "));
+            }
+            let missing = results.iter().filter(|status| !status.installed).count();
+            let outdated = results.iter().filter(|status| status.is_outdated()).count();
+            if outdated_only {
+                results.retain(|status| status.is_outdated());
+            }
+            sort_results(&mut results, sort, reverse);
+            match cli.format {
+                OutputFormat::Table => print_results(results, latest, table_style),
+                format => print_serialized(&results, format)?,
+            }
+            let mut failures = vec![];
+            if fail_if_missing && missing > 0 {
+                failures.push(format!("{} missing", missing));
+            }
+            if fail_if_outdated && outdated > 0 {
+                failures.push(format!("{} outdated", outdated));
+            }
+            if !failures.is_empty() {
+                return Err(BinaError::CheckFailed(format!("Check failed: {}", failures.join(", "))));
+            }
+        }
+        Some(Commands::List { .. }) => match cli.format {
+            OutputFormat::Table => print_config(&manager, table_style),
+            format => {
+                let binaries: Vec<&Binary> = manager.data.values().filter(|binary| manager.selects(binary)).collect();
+                print_serialized(&binaries, format)?;
+            }
+        },
+        Some(Commands::Info { bin_name }) => {
+            let info = binary_info(&bin_name, &manager).await?;
+            match cli.format {
+                OutputFormat::Table => print_info(&info),
+                format => print_serialized(&info, format)?,
+            }
+        }
+        Some(Commands::Which { bin_name }) => {
+            let which = which_binary(&bin_name, &manager)?;
+            match cli.format {
+                OutputFormat::Table => print_which(&which),
+                format => print_serialized(&which, format)?,
+            }
+        }
+        Some(Commands::Get {
+            bin_name,
+            locked,
+            tag,
+            assets_dir,
+            ..
+        }) => {
+            let (bin_name, tag) = match bin_name.split_once('@') {
+                Some(_) if tag.is_some() => return Err("Use either name@tag or --tag, not both".into()),
+                Some(_) if locked => return Err("name@tag cannot be combined with --locked".into()),
+                Some((bin_name, tag)) => (bin_name.to_string(), Some(tag.to_string())),
+                None => (bin_name, tag),
+            };
+            manager.offline_assets = assets_dir.map(PathBuf::from);
+            get_binary(&bin_name, &manager, locked, tag.as_deref()).await?;
+            if !manager.dry_run {
+                release_hold(&bin_name)?;
+            }
+        }
+        Some(Commands::Run { bin_name, args }) => {
+            let code = run_binary(&bin_name, &args, &manager).await?;
+            process::exit(code);
+        }
+        Some(Commands::GetMissing { locked, jobs, .. }) => {
+            let mut summary = BatchSummary::new(cli.dry_run);
+            let result = get_missing_binaries(&manager, locked, jobs, &mut summary).await;
+            finish_batch(&summary, result)?;
+        }
+        Some(Commands::Add {
+            repo,
+            name,
+            exe,
+            version_arg,
+            install,
+        }) => {
+            let bin_name = add_binary(&repo, name, exe, version_arg, cli.dry_run)?;
+            if install && cli.dry_run {
+                info!("Would install {}", bin_name);
+            } else if install {
+                manager.reload_binaries()?;
+                get_binary(&bin_name, &manager, false, None).await?;
+            }
+        }
+        Some(Commands::Search { query, limit }) => {
+            let results = search_repositories(&query, limit, &manager).await?;
+            match cli.format {
+                OutputFormat::Table if results.is_empty() => println!("No repositories match '{}'", query),
+                OutputFormat::Table => {
+                    print_search_results(&results, table_style);
+                    if io::stdin().is_terminal()
+                        && let Some(result) = choose_search_result(&results)?
+                    {
+                        add_binary(&result.repo, None, None, None, cli.dry_run)?;
+                    }
+                }
+                format => print_serialized(&results, format)?,
+            }
+        }
+        Some(Commands::Remove { bin_name, purge }) => {
+            remove_binary(&bin_name, purge, &manager)?;
+        }
+        Some(Commands::Update { no_notes, .. }) => {
+            let mut summary = BatchSummary::new(cli.dry_run);
+            let result = update_binaries(&manager, !no_notes, &mut summary).await;
+            finish_batch(&summary, result)?;
+        }
+        Some(Commands::Watch { interval, notify, .. }) => {
+            watch_releases(&manager, interval, notify).await?;
+        }
+        Some(Commands::Rollback { bin_name, to }) => {
+            rollback_binary(&bin_name, to.as_deref(), &manager)?;
+        }
+        Some(Commands::SelfUpdate) => {
+            println!("{}", self_update(&manager).await?);
+        }
+        Some(Commands::Tui { .. }) => {
+            run_tui(&manager).await?;
+        }
+        Some(Commands::Sbom) => {
+            let entries = sbom_entries(&manager).await?;
+            match cli.format {
+                OutputFormat::Table => print_sbom(&entries, table_style),
+                OutputFormat::Cyclonedx => {
+                    println!("{}", serde_json::to_string_pretty(&cyclonedx_sbom(&entries, &manager))?)
+                }
+                format => print_serialized(&entries, format)?,
+            }
+        }
+        Some(Commands::Audit) => {
+            let mut summary = BatchSummary::new(cli.dry_run);
+            let vulnerabilities = audit_binaries(&manager, &mut summary).await?;
+            match cli.format {
+                OutputFormat::Table if vulnerabilities.is_empty() && summary.failed.is_empty() => {
+                    println!("No known vulnerabilities")
+                }
+                OutputFormat::Table if vulnerabilities.is_empty() => {
+                    println!("No known vulnerabilities in the binaries that could be audited")
+                }
+                OutputFormat::Table => print_vulnerabilities(&vulnerabilities, table_style),
+                format => print_serialized(&vulnerabilities, format)?,
+            }
+            for failure in &summary.failed {
+                warn!("{}", failure);
+            }
+            if !vulnerabilities.is_empty() {
+                let mut binaries: Vec<&str> = vulnerabilities.iter().map(|v| v.binary.as_str()).collect();
+                binaries.dedup();
+                return Err(BinaError::CheckFailed(format!(
+                    "{} vulnerabilities affect {}",
+                    vulnerabilities.len(),
+                    binaries.join(", ")
+                )));
+            }
+            if !summary.failed.is_empty() {
+                return Err(format!("{} binaries could not be audited", summary.failed.len()).into());
+            }
         }
-        Some(Commands::Get { bin_name }) => {
-            get_binary(&bin_name, &manager).await?;
+        Some(Commands::Prune { yes }) => {
+            println!("{}", prune_binaries(&manager, yes)?);
         }
-        Some(Commands::GetMissing) => {
-            let result = get_missing_binaries(&manager).await?;
-            if !result.is_empty() {
-                println!("{}", result);
+        Some(Commands::Sync { prune, jobs, .. }) => {
+            let mut summary = BatchSummary::new(cli.dry_run);
+            let result = sync_binaries(&manager, jobs, &mut summary).await;
+            if prune && result.is_ok() {
+                println!("{}", prune_binaries(&manager, true)?);
             }
+            finish_batch(&summary, result)?;
         }
         Some(Commands::LinkConfig) => {
-            link_config_directory()?;
+            link_config_directory(cli.dry_run)?;
         }
-        None => {
-            Cli::parse_from(&["bina", "--help"]);
+        Some(
+            Commands::Completions { .. }
+            | Commands::Man { .. }
+            | Commands::Doctor
+            | Commands::Config { .. }
+            | Commands::Cache { .. }
+            | Commands::Schedule { .. }
+            | Commands::Init { .. }
+            | Commands::Import { .. }
+            | Commands::Export,
+        ) => {
+            unreachable!("handled before loading the manager")
         }
+        None => unreachable!("main defaults to check"),
     }
     Ok(())
 }